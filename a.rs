@@ -59,7 +59,7 @@ fn try_move(
     x: usize, y: usize,
     v: &[Vec<char>], h: &[Vec<char>], dir: char,
 ) -> Option<(usize, usize)> {
-    let n = 30;
+    let n = v.len();
     let (nx, ny) = match dir {
         'U' => (x.wrapping_sub(1), y),
         'D' => (x.wrapping_add(1), y),
@@ -90,7 +90,7 @@ fn calc_bitboard(
     ij: &[(usize, usize)], v: &[Vec<char>], h: &[Vec<char>],
     alloc: &[Vec<char>], ops: &[usize],
 ) -> (Vec<u32>, Vec<(usize, usize)>) {
-    let n = 30;
+    let n = v.len();
     let mut bitboard = vec![0; n];
     let mut pts = vec![];
     for (i, row) in alloc.iter().enumerate() {
@@ -114,7 +114,7 @@ fn calc_distance(
     bitboard: &[u32],
     que: &mut VecDeque<(i32, usize, usize)>,
 ) -> Vec<Vec<i32>> {
-    let n = 30;
+    let n = v.len();
     let mut dist = vec![vec![1000; n]; n];
     for i in 0..n {
         for j in 0..n {
@@ -166,25 +166,251 @@ fn calc_distance(
     dist
 }
 
+// Admissible lower bound on the number of turns needed to visit every cell:
+// each turn visits at most m new cells, and every unvisited cell is at least
+// its BFS distance away from the nearest robot. The BFS part is only computed
+// when the cheap bound is close to `limit`.
+fn remaining_lower_bound(
+    n: usize, m: usize,
+    v: &[Vec<char>], h: &[Vec<char>],
+    bitboard: &[u32], pts: &[(usize, usize)],
+    limit: usize,
+    que: &mut VecDeque<(i32, usize, usize)>,
+) -> usize {
+    let mut rem = 0;
+    for i in 0..n {
+        rem += n - bitboard[i].count_ones() as usize;
+    }
+    let lb = (rem + m - 1) / m;
+    // The BFS bound rarely exceeds 2n.
+    if lb + 2 * n < limit {
+        return lb;
+    }
+    let dist = bfs(v, h, pts, que);
+    let mut far = 0;
+    for i in 0..n {
+        for j in 0..n {
+            if (bitboard[i] >> j) & 1 == 0 {
+                far.chmax(dist[i][j] as usize);
+            }
+        }
+    }
+    far.max(lb)
+}
+
+/// How buttons are evaluated in a rollout. Selected with the `PLANNER`
+/// environment variable (`nearest` or `matching`).
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Planner {
+    /// Each robot heads for its nearest unvisited cell.
+    Nearest,
+    /// Unvisited cells are clustered, clusters are matched to robots, and
+    /// each robot heads for its own cluster.
+    Matching,
+}
+
+impl Planner {
+    fn from_env() -> Planner {
+        match std::env::var("PLANNER").as_deref() {
+            Err(_) | Ok("nearest") => Planner::Nearest,
+            Ok("matching") => Planner::Matching,
+            Ok(s) => panic!("unknown PLANNER: {}", s),
+        }
+    }
+}
+
+/// A cluster of unvisited cells assigned to one robot.
+#[derive(Clone)]
+struct Target {
+    cells: Vec<(usize, usize)>,
+    /// BFS distance to the nearest cell of the cluster.
+    dist: Vec<Vec<i32>>,
+    /// The robot has reached the cluster and no longer follows `dist`.
+    arrived: bool,
+}
+
+impl Target {
+    fn is_done(&self, bitboard: &[u32]) -> bool {
+        self.cells.iter().all(|&(x, y)| (bitboard[x] >> y) & 1 == 1)
+    }
+}
+
+fn bfs(
+    v: &[Vec<char>], h: &[Vec<char>],
+    sources: &[(usize, usize)],
+    que: &mut VecDeque<(i32, usize, usize)>,
+) -> Vec<Vec<i32>> {
+    let n = v.len();
+    let mut dist = vec![vec![1000; n]; n];
+    for &(x, y) in sources {
+        dist[x][y] = 0;
+        que.push_back((0, x, y));
+    }
+    while let Some((d, x, y)) = que.pop_front() {
+        if dist[x][y] < d {
+            continue;
+        }
+        for dir in ['U', 'D', 'L', 'R'] {
+            if let Some((nx, ny)) = try_move(x, y, v, h, dir) {
+                if dist[nx][ny] > d + 1 {
+                    dist[nx][ny] = d + 1;
+                    que.push_back((d + 1, nx, ny));
+                }
+            }
+        }
+    }
+    dist
+}
+
+// Min-cost assignment of rows to distinct columns (rows <= cols).
+// Returns the column assigned to each row.
+fn hungarian(a: &[Vec<i64>]) -> Vec<usize> {
+    let n = a.len();
+    if n == 0 {
+        return vec![];
+    }
+    let m = a[0].len();
+    assert!(n <= m);
+    const INF: i64 = 1 << 60;
+    let mut u = vec![0; n + 1];
+    let mut v = vec![0; m + 1];
+    let mut p = vec![0; m + 1];
+    let mut way = vec![0; m + 1];
+    for i in 1..=n {
+        p[0] = i;
+        let mut j0 = 0;
+        let mut minv = vec![INF; m + 1];
+        let mut used = vec![false; m + 1];
+        loop {
+            used[j0] = true;
+            let i0 = p[j0];
+            let mut delta = INF;
+            let mut j1 = 0;
+            for j in 1..=m {
+                if !used[j] {
+                    let cur = a[i0 - 1][j - 1] - u[i0] - v[j];
+                    if cur < minv[j] {
+                        minv[j] = cur;
+                        way[j] = j0;
+                    }
+                    if minv[j] < delta {
+                        delta = minv[j];
+                        j1 = j;
+                    }
+                }
+            }
+            for j in 0..=m {
+                if used[j] {
+                    u[p[j]] += delta;
+                    v[j] -= delta;
+                } else {
+                    minv[j] -= delta;
+                }
+            }
+            j0 = j1;
+            if p[j0] == 0 {
+                break;
+            }
+        }
+        loop {
+            let j1 = way[j0];
+            p[j0] = p[j1];
+            j0 = j1;
+            if j0 == 0 {
+                break;
+            }
+        }
+    }
+    let mut ans = vec![0; n];
+    for j in 1..=m {
+        if p[j] != 0 {
+            ans[p[j] - 1] = j - 1;
+        }
+    }
+    ans
+}
+
+// Splits unvisited cells into at most m clusters (farthest-point seeding on
+// Manhattan distance), matches clusters to robots by BFS distance, and
+// returns for each robot its cluster (None if the robot got no cluster).
+fn calc_targets(
+    v: &[Vec<char>], h: &[Vec<char>],
+    bitboard: &[u32], pts: &[(usize, usize)],
+    que: &mut VecDeque<(i32, usize, usize)>,
+) -> Vec<Option<Target>> {
+    let n = v.len();
+    let m = pts.len();
+    let mut cells = vec![];
+    for i in 0..n {
+        for j in 0..n {
+            if (bitboard[i] >> j) & 1 == 0 {
+                cells.push((i, j));
+            }
+        }
+    }
+    let c = m.min(cells.len());
+    if c == 0 {
+        return vec![None; m];
+    }
+    let manhattan = |a: (usize, usize), b: (usize, usize)| {
+        a.0.abs_diff(b.0) + a.1.abs_diff(b.1)
+    };
+    let mut centers = vec![cells[0]];
+    let mut near = vec![0; cells.len()];
+    let mut near_dist: Vec<usize> = cells.iter().map(|&p| manhattan(p, cells[0])).collect();
+    while centers.len() < c {
+        let far = (0..cells.len()).max_by_key(|&i| near_dist[i]).unwrap();
+        if near_dist[far] == 0 {
+            break;
+        }
+        let idx = centers.len();
+        centers.push(cells[far]);
+        for i in 0..cells.len() {
+            let d = manhattan(cells[i], cells[far]);
+            if d < near_dist[i] {
+                near_dist[i] = d;
+                near[i] = idx;
+            }
+        }
+    }
+    let c = centers.len();
+    let mut clusters = vec![vec![]; c];
+    for i in 0..cells.len() {
+        clusters[near[i]].push(cells[i]);
+    }
+    let fields: Vec<_> = clusters.iter().map(|cl| bfs(v, h, cl, que)).collect();
+    let cost: Vec<Vec<i64>> = fields.iter()
+        .map(|f| pts.iter().map(|&(x, y)| f[x][y] as i64).collect())
+        .collect();
+    let assign = hungarian(&cost);
+    let mut targets = vec![None; m];
+    for ((cells, dist), robot) in clusters.into_iter().zip(fields).zip(assign) {
+        targets[robot] = Some(Target { cells, dist, arrived: false });
+    }
+    targets
+}
+
 fn try_once(
     n: usize, m: usize, k: usize,
     ij: &[(usize, usize)],
     v: &[Vec<char>],
     h: &[Vec<char>],
     rng: &mut Rng,
+    planner: Planner,
     cutoff: u32,
 ) -> (u32, Vec<Vec<char>>, Vec<usize>) {
-    let mut alloc = vec![vec!['D'; k]; m];
+    let mut alloc = vec![vec![]; m];
     for i in 0..m {
-        for j in 0..10 {
-            alloc[i][j] = b"UDLR"[j % 4] as char;
-        }
-        for j in 1..k {
+        // With fewer than 4 buttons, each robot gets a random subset of UDLR.
+        let len = k.max(4);
+        alloc[i] = (0..len).map(|j| b"UDLR"[j % 4] as char).collect();
+        for j in 1..len {
             let r = rng.next() as usize % (j + 1);
             alloc[i].swap(r, j);
         }
+        alloc[i].truncate(k);
     }
-    try_once_with_alloc(n, m, k, ij, v, h, &alloc, cutoff)
+    try_once_with_alloc(n, m, k, ij, v, h, &alloc, planner, cutoff)
 }
 
 fn try_once_with_alloc(
@@ -193,34 +419,82 @@ fn try_once_with_alloc(
     v: &[Vec<char>],
     h: &[Vec<char>],
     alloc: &[Vec<char>],
+    planner: Planner,
     cutoff: u32,
 ) -> (u32, Vec<Vec<char>>, Vec<usize>) {
-    let max_turns = 3 * n * n - cutoff as usize;
+    // At most 2n^2 actions are allowed.
+    let max_turns = (3 * n * n - cutoff as usize).min(2 * n * n);
     let mut ops = vec![];
     let mut que = VecDeque::new();
-    for _ in 0..max_turns {
-        let (now_bb, now_pts) = calc_bitboard(ij, v, h, &alloc, &ops);
+    let mut covered = 0;
+    let mut last_progress = 0;
+    // Robots with all of UDLR can reach every cell, so only a rollout where
+    // some robot lacks a direction can get stuck for good.
+    let may_stall = alloc.iter().any(|a| b"UDLR".iter().any(|&c| !a.contains(&(c as char))));
+    let mut targets = vec![None; m];
+    let (mut now_bb, mut now_pts) = calc_bitboard(ij, v, h, alloc, &ops);
+    for t in 0..max_turns {
         if (0..n).all(|x| now_bb[x] == (1 << n) - 1) {
             break;
         }
+        // Some cells may be unreachable with this alloc (e.g. when K < 4);
+        // stop once the robots are stuck.
+        let now_covered: u32 = now_bb.iter().map(|b| b.count_ones()).sum();
+        if now_covered > covered {
+            covered = now_covered;
+            last_progress = t;
+        } else if may_stall && t - last_progress >= 4 * n {
+            break;
+        }
+        // Once the incumbent visits every cell, an incomplete rollout can never
+        // beat it, so give up as soon as finishing in time is impossible.
+        if cutoff as usize > n * n
+            && ops.len() + remaining_lower_bound(
+                n, m, v, h, &now_bb, &now_pts, max_turns - ops.len(), &mut que,
+            ) >= max_turns {
+            return (0, alloc.to_vec(), ops);
+        }
         let dist = calc_distance(v, h, &now_bb, &mut que);
-        let mut best = (vec![1 << 30], 0);
+        if planner == Planner::Matching {
+            // Clusters are only rebuilt once some robot has finished its own.
+            let stale = targets.iter().all(Option::is_none)
+                || targets.iter().flatten().any(|t: &Target| t.is_done(&now_bb));
+            if stale {
+                targets = calc_targets(v, h, &now_bb, &now_pts, &mut que);
+            }
+            // Once in its cluster, a robot just heads for unvisited cells.
+            for (t, &(x, y)) in targets.iter_mut().zip(&now_pts) {
+                if let Some(t) = t.as_mut().filter(|t| t.dist[x][y] == 0) {
+                    t.arrived = true;
+                }
+            }
+        }
+        let mut best = (1 << 30, vec![1 << 30], 0);
         for i in 0..k {
+            let mut progress = 0;
             let mut sum = vec![];
             for j in 0..m {
                 let np = try_move(now_pts[j].0, now_pts[j].1, v, h, alloc[j][i]);
                 let np = np.unwrap_or(now_pts[j]);
+                if let Some(target) = targets[j].as_ref().filter(|t| !t.arrived) {
+                    progress += target.dist[np.0][np.1];
+                }
                 sum.push(dist[np.0][np.1]);
             }
             sum.sort_unstable();
-            best = best.min((sum, i));
+            best = best.min((progress, sum, i));
+        }
+        ops.push(best.2);
+        for (j, p) in now_pts.iter_mut().enumerate() {
+            if let Some(np) = try_move(p.0, p.1, v, h, alloc[j][best.2]) {
+                *p = np;
+            }
+            now_bb[p.0] |= 1 << p.1;
         }
-        ops.push(best.1);
     }
-    let (bitboard, _) = calc_bitboard(ij, v, h, &alloc, &ops);
     let mut score = 0;
     for i in 0..n {
-        score += bitboard[i].count_ones();
+        score += now_bb[i].count_ones();
     }
     if score as usize == n * n {
         score = (3 * n * n - ops.len()) as u32;
@@ -228,6 +502,57 @@ fn try_once_with_alloc(
     (score, alloc.to_vec(), ops)
 }
 
+// Multi-start over random allocations, then hill climbing by swapping
+// directions. Returns the best allocation (per robot) and actions.
+fn solve(
+    n: usize, m: usize, k: usize,
+    ij: &[(usize, usize)],
+    v: &[Vec<char>],
+    h: &[Vec<char>],
+    planner: Planner,
+) -> (Vec<Vec<char>>, Vec<usize>) {
+    let mut rng = Rng { x: 0xdead_c0de_0013_3331u64 };
+    let mut best_score = 0;
+    let mut best_alloc = vec![vec!['D'; k]; m];
+    let mut best_ops = vec![];
+    for _ in 0..50 {
+        let (score, alloc, ops) = try_once(n, m, k, ij, v, h, &mut rng, planner, best_score);
+        if score > best_score {
+            eprintln!("start: {best_score} -> {score}");
+            best_score = score;
+            best_alloc = alloc;
+            best_ops = ops;
+        }
+    }
+    for _ in 0..138 {
+        let mut alloc = best_alloc.to_vec();
+        let idx = rng.next() as usize % m;
+        if k == 1 {
+            // Nothing to swap; change the only direction instead.
+            alloc[idx][0] = b"UDLR"[rng.next() as usize % 4] as char;
+        } else {
+            let x = rng.next() as usize % k;
+            let y = rng.next() as usize % (k - 1) + 1;
+            let y = (x + y) % k;
+            alloc[idx].swap(x, y);
+            if m > 1 {
+                let idx = idx + (rng.next() as usize % (m - 1)) + 1;
+                let idx = idx % m;
+                alloc[idx].swap(x, y);
+            }
+        }
+        let (score, alloc, ops) = try_once_with_alloc(n, m, k, ij, v, h, &alloc, planner, best_score);
+        if score > best_score {
+            eprintln!("climb: {best_score} -> {score}");
+            best_score = score;
+            best_alloc = alloc;
+            best_ops = ops;
+        }
+    }
+    eprintln!("score = {best_score}");
+    (best_alloc, best_ops)
+}
+
 fn main() {
     let out = std::io::stdout();
     let mut out = BufWriter::new(out.lock());
@@ -252,38 +577,7 @@ fn main() {
         v: [chars; n],
         h: [chars; n - 1],
     }
-    let mut rng = Rng { x: 0xdead_c0de_0013_3331u64 };
-    let mut best_score = 0;
-    let mut best_alloc = vec![vec!['D'; k]; m];
-    let mut best_ops = vec![];
-    for _ in 0..50 {
-        let (score, alloc, ops) = try_once(n, m, k, &ij, &v, &h, &mut rng, best_score);
-        if score > best_score {
-            eprintln!("start: {best_score} -> {score}");
-            best_score = score;
-            best_alloc = alloc;
-            best_ops = ops;
-        }
-    }
-    for _ in 0..138 {
-        let mut alloc = best_alloc.to_vec();
-        let idx = rng.next() as usize % m;
-        let x = rng.next() as usize % k;
-        let y = rng.next() as usize % (k - 1) + 1;
-        let y = (x + y) % k;
-        alloc[idx].swap(x, y);
-        let idx = idx + (rng.next() as usize % (m - 1)) + 1;
-        let idx = idx % m;
-        alloc[idx].swap(x, y);
-        let (score, alloc, ops) = try_once_with_alloc(n, m, k, &ij, &v, &h, &alloc, best_score);
-        if score > best_score {
-            eprintln!("climb: {best_score} -> {score}");
-            best_score = score;
-            best_alloc = alloc;
-            best_ops = ops;
-        }
-    }
-    eprintln!("score = {best_score}");
+    let (best_alloc, best_ops) = solve(n, m, k, &ij, &v, &h, Planner::from_env());
     // emit ans
     for i in 0..k {
         let mut tmp = vec![];
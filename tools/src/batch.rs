@@ -0,0 +1,411 @@
+//! Running a solver over many inputs and summarizing the results.
+
+use crate::*;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Result of running a solver on one input.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CaseResult {
+    /// File name of the input, e.g. `0000.txt`.
+    #[cfg_attr(feature = "serde", serde(rename = "file"))]
+    pub name: String,
+    pub score: i64,
+    /// Number of actions in the output.
+    pub turns: usize,
+    /// Number of visited cells.
+    pub coverage: usize,
+    pub time_ms: u64,
+    /// Empty if the output was accepted.
+    pub error: String,
+}
+
+/// Runs `solver` with `input_file` as stdin, writes its stdout to `output_file`
+/// and scores it. The solver is killed after `timeout`.
+pub fn run_case(solver: &Path, input_file: &Path, output_file: &Path, timeout: Duration) -> CaseResult {
+    let name = input_file
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .into_owned();
+    let mut res = CaseResult {
+        name,
+        score: 0,
+        turns: 0,
+        coverage: 0,
+        time_ms: 0,
+        error: String::new(),
+    };
+    let input = match std::fs::read_to_string(input_file) {
+        Ok(input) => match parse_input(&input) {
+            Ok(input) => input,
+            Err(err) => {
+                res.error = format!("cannot parse input: {}", err);
+                return res;
+            }
+        },
+        Err(err) => {
+            res.error = format!("cannot read input: {}", err);
+            return res;
+        }
+    };
+    let spawned = std::fs::File::open(input_file).and_then(|stdin| {
+        let stdout = std::fs::File::create(output_file)?;
+        Command::new(solver)
+            .stdin(stdin)
+            .stdout(stdout)
+            .stderr(Stdio::null())
+            .spawn()
+    });
+    let mut child = match spawned {
+        Ok(child) => child,
+        Err(err) => {
+            res.error = format!("cannot run solver: {}", err);
+            return res;
+        }
+    };
+    let start = Instant::now();
+    loop {
+        match child.try_wait() {
+            Ok(Some(status)) => {
+                if !status.success() {
+                    res.error = format!("solver failed: {}", status);
+                }
+                break;
+            }
+            Ok(None) if start.elapsed() > timeout => {
+                let _ = child.kill();
+                let _ = child.wait();
+                res.error = "Timeout".to_owned();
+                break;
+            }
+            Ok(None) => std::thread::sleep(Duration::from_millis(5)),
+            Err(err) => {
+                res.error = format!("solver failed: {}", err);
+                break;
+            }
+        }
+    }
+    res.time_ms = start.elapsed().as_millis() as u64;
+    let output = std::fs::read_to_string(output_file).unwrap_or_default();
+    match parse_output(&input, &output) {
+        Ok(out) => {
+            let (score, err) = compute_score(&input, &out);
+            res.turns = out.actions.len();
+            res.coverage = if score > (input.N * input.N) as i64 {
+                input.N * input.N
+            } else {
+                score as usize
+            };
+            if res.error.is_empty() {
+                res.score = score;
+                res.error = err.map(|e| e.to_string()).unwrap_or_default();
+            }
+        }
+        Err(err) => {
+            if res.error.is_empty() {
+                res.error = err.to_string();
+            }
+        }
+    }
+    res
+}
+
+pub fn default_jobs() -> usize {
+    std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1)
+}
+
+/// Runs `solver` on every file in `in_dir` with `jobs` cases in parallel.
+/// Outputs are written to `out_dir` under the same file names.
+pub fn run_all(
+    solver: &Path,
+    in_dir: &Path,
+    out_dir: &Path,
+    timeout: Duration,
+    jobs: usize,
+) -> std::io::Result<Vec<CaseResult>> {
+    if !out_dir.exists() {
+        std::fs::create_dir_all(out_dir)?;
+    }
+    let mut files = vec![];
+    for e in std::fs::read_dir(in_dir)? {
+        let path = e?.path();
+        if path.is_file() {
+            files.push(path);
+        }
+    }
+    files.sort();
+    let next = Mutex::new(0);
+    let results = Mutex::new(vec![None; files.len()]);
+    std::thread::scope(|s| {
+        for _ in 0..jobs.max(1) {
+            s.spawn(|| loop {
+                let id = {
+                    let mut next = next.lock().unwrap();
+                    *next += 1;
+                    *next - 1
+                };
+                if id >= files.len() {
+                    break;
+                }
+                let out_file = out_dir.join(files[id].file_name().unwrap());
+                let res = run_case(solver, &files[id], &out_file, timeout);
+                eprintln!(
+                    "{}: score = {}, turns = {}, {} ms {}",
+                    res.name, res.score, res.turns, res.time_ms, res.error
+                );
+                results.lock().unwrap()[id] = Some(res);
+            });
+        }
+    });
+    Ok(results
+        .into_inner()
+        .unwrap()
+        .into_iter()
+        .map(Option::unwrap)
+        .collect())
+}
+
+const CSV_HEADER: &str = "file,score,turns,coverage,time_ms,error";
+
+/// Writes results in CSV. Commas and newlines in error messages are replaced
+/// with spaces.
+pub fn write_csv(w: &mut impl std::io::Write, results: &[CaseResult]) -> std::io::Result<()> {
+    writeln!(w, "{}", CSV_HEADER)?;
+    for r in results {
+        writeln!(
+            w,
+            "{},{},{},{},{},{}",
+            r.name,
+            r.score,
+            r.turns,
+            r.coverage,
+            r.time_ms,
+            r.error.replace([',', '\n', '\r'], " ")
+        )?;
+    }
+    Ok(())
+}
+
+pub fn read_csv(f: &str) -> Result<Vec<CaseResult>, String> {
+    let mut lines = f.lines();
+    if lines.next().map(|l| l.trim()) != Some(CSV_HEADER) {
+        return Err(format!("Expected header: {}", CSV_HEADER));
+    }
+    let mut results = vec![];
+    for line in lines {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let mut f = line.splitn(6, ',');
+        let name = f.next().unwrap().to_owned();
+        let score = read(f.next(), ..)?;
+        let turns = read(f.next(), ..)?;
+        let coverage = read(f.next(), ..)?;
+        let time_ms = read(f.next(), ..)?;
+        let error = f.next().unwrap_or("").to_owned();
+        results.push(CaseResult {
+            name,
+            score,
+            turns,
+            coverage,
+            time_ms,
+            error,
+        });
+    }
+    Ok(results)
+}
+
+#[cfg(feature = "serde")]
+pub fn write_json(w: &mut impl std::io::Write, results: &[CaseResult]) -> std::io::Result<()> {
+    serde_json::to_writer_pretty(&mut *w, results)?;
+    writeln!(w)
+}
+
+/// Reads results written by `write_json`.
+#[cfg(feature = "serde")]
+pub fn read_json(f: &str) -> Result<Vec<CaseResult>, String> {
+    serde_json::from_str(f).map_err(|err| err.to_string())
+}
+
+/// Paired comparison of two result sets on the same inputs.
+#[derive(Clone, Debug)]
+pub struct Comparison {
+    /// (name, score of A, score of B) for inputs present in both.
+    pub pairs: Vec<(String, i64, i64)>,
+    pub mean_delta: f64,
+    pub median_delta: f64,
+    /// Number of inputs where B is better / worse / equal.
+    pub wins: usize,
+    pub losses: usize,
+    pub ties: usize,
+    /// Two-sided p-value of the Wilcoxon signed-rank test (normal approximation).
+    pub p_value: f64,
+}
+
+/// Compares `b` against `a`; deltas are `b - a`.
+pub fn compare(a: &[CaseResult], b: &[CaseResult]) -> Comparison {
+    let mut pairs = vec![];
+    for ra in a {
+        if let Some(rb) = b.iter().find(|rb| rb.name == ra.name) {
+            pairs.push((ra.name.clone(), ra.score, rb.score));
+        }
+    }
+    let deltas = pairs.iter().map(|&(_, a, b)| b - a).collect::<Vec<_>>();
+    let n = deltas.len();
+    let mean_delta = if n == 0 {
+        0.0
+    } else {
+        deltas.iter().sum::<i64>() as f64 / n as f64
+    };
+    let mut sorted = deltas.clone();
+    sorted.sort();
+    let median_delta = if n == 0 {
+        0.0
+    } else if n % 2 == 1 {
+        sorted[n / 2] as f64
+    } else {
+        (sorted[n / 2 - 1] + sorted[n / 2]) as f64 / 2.0
+    };
+    Comparison {
+        mean_delta,
+        median_delta,
+        wins: deltas.iter().filter(|&&d| d > 0).count(),
+        losses: deltas.iter().filter(|&&d| d < 0).count(),
+        ties: deltas.iter().filter(|&&d| d == 0).count(),
+        p_value: wilcoxon_signed_rank(&deltas),
+        pairs,
+    }
+}
+
+/// Two-sided p-value of the Wilcoxon signed-rank test with tie correction.
+/// Zero differences are dropped.
+pub fn wilcoxon_signed_rank(deltas: &[i64]) -> f64 {
+    let mut abs = deltas
+        .iter()
+        .filter(|&&d| d != 0)
+        .map(|&d| (d.abs(), d > 0))
+        .collect::<Vec<_>>();
+    abs.sort();
+    let n = abs.len();
+    if n == 0 {
+        return 1.0;
+    }
+    let mut w_plus = 0.0;
+    let mut tie_sum = 0.0;
+    let mut i = 0;
+    while i < n {
+        let mut j = i;
+        while j < n && abs[j].0 == abs[i].0 {
+            j += 1;
+        }
+        // Ranks i + 1 ..= j share their average.
+        let rank = (i + 1 + j) as f64 / 2.0;
+        w_plus += rank * abs[i..j].iter().filter(|x| x.1).count() as f64;
+        let t = (j - i) as f64;
+        tie_sum += t * t * t - t;
+        i = j;
+    }
+    let n = n as f64;
+    let mean = n * (n + 1.0) / 4.0;
+    let var = n * (n + 1.0) * (2.0 * n + 1.0) / 24.0 - tie_sum / 48.0;
+    if var <= 0.0 {
+        return 1.0;
+    }
+    let z = ((w_plus - mean).abs() - 0.5).max(0.0) / var.sqrt();
+    (2.0 * (1.0 - normal_cdf(z))).min(1.0)
+}
+
+/// Standard normal CDF (Abramowitz and Stegun 7.1.26, error < 1.5e-7).
+pub fn normal_cdf(z: f64) -> f64 {
+    let x = z.abs() / std::f64::consts::SQRT_2;
+    let t = 1.0 / (1.0 + 0.3275911 * x);
+    let poly = t
+        * (0.254829592 + t * (-0.284496736 + t * (1.421413741 + t * (-1.453152027 + t * 1.061405429))));
+    let erf = 1.0 - poly * (-x * x).exp();
+    if z >= 0.0 {
+        0.5 * (1.0 + erf)
+    } else {
+        0.5 * (1.0 - erf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(name: &str, score: i64) -> CaseResult {
+        CaseResult {
+            name: name.to_owned(),
+            score,
+            turns: 0,
+            coverage: 0,
+            time_ms: 0,
+            error: String::new(),
+        }
+    }
+
+    #[test]
+    fn csv_round_trip() {
+        let mut r = result("0000.txt", 2451);
+        r.error = "Parse error: x, y".to_owned();
+        let mut buf = vec![];
+        write_csv(&mut buf, &[r]).unwrap();
+        let rs = read_csv(&String::from_utf8(buf).unwrap()).unwrap();
+        assert_eq!(rs.len(), 1);
+        assert_eq!(rs[0].name, "0000.txt");
+        assert_eq!(rs[0].score, 2451);
+        assert_eq!(rs[0].error, "Parse error: x  y");
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn json_round_trip() {
+        let mut r = result("0000.txt", 2451);
+        r.error = "Parse error: \"x\",\ny\u{1}".to_owned();
+        r.turns = 249;
+        let mut buf = vec![];
+        write_json(&mut buf, &[r.clone(), result("0001.txt", -1)]).unwrap();
+        let json = String::from_utf8(buf).unwrap();
+        assert!(json.contains("\"file\": \"0000.txt\""));
+        let rs = read_json(&json).unwrap();
+        assert_eq!(rs.len(), 2);
+        assert_eq!(rs[0].name, "0000.txt");
+        assert_eq!((rs[0].score, rs[0].turns), (2451, 249));
+        assert_eq!(rs[0].error, r.error);
+        assert_eq!(rs[1].score, -1);
+        assert!(read_json("[]").unwrap().is_empty());
+        assert!(read_json("[{\"score\": 1}]")
+            .unwrap_err()
+            .contains("missing field `file`"));
+    }
+
+    #[test]
+    fn normal_cdf_values() {
+        assert!((normal_cdf(0.0) - 0.5).abs() < 1e-6);
+        assert!((normal_cdf(1.96) - 0.975).abs() < 1e-4);
+        assert!((normal_cdf(-1.0) - 0.158655).abs() < 1e-4);
+    }
+
+    #[test]
+    fn compare_paired() {
+        let a = (0..20).map(|i| result(&i.to_string(), 100)).collect::<Vec<_>>();
+        let mut b = (0..20)
+            .map(|i| result(&i.to_string(), 100 + i))
+            .collect::<Vec<_>>();
+        b.push(result("only b", 0));
+        let c = compare(&a, &b);
+        assert_eq!(c.pairs.len(), 20);
+        assert_eq!((c.wins, c.losses, c.ties), (19, 0, 1));
+        assert!((c.mean_delta - 9.5).abs() < 1e-9);
+        assert!((c.median_delta - 9.5).abs() < 1e-9);
+        assert!(c.p_value < 0.001);
+        let c = compare(&a, &a);
+        assert_eq!(c.p_value, 1.0);
+    }
+}
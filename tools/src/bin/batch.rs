@@ -0,0 +1,56 @@
+#![allow(non_snake_case)]
+
+use clap::Parser;
+use std::path::PathBuf;
+use std::time::Duration;
+use tools::batch::*;
+
+#[derive(Parser, Debug)]
+struct Cli {
+    /// Path to the solver executable
+    solver: PathBuf,
+    /// Path to input directory
+    #[clap(short = 'i', long = "in", default_value = "in")]
+    in_dir: PathBuf,
+    /// Path to output directory
+    #[clap(short = 'o', long = "out", default_value = "out")]
+    out_dir: PathBuf,
+    /// Number of cases to run in parallel (default: number of CPUs)
+    #[clap(short, long)]
+    jobs: Option<usize>,
+    /// Time limit per case in seconds
+    #[clap(short, long, default_value_t = 10.0)]
+    timeout: f64,
+    /// Path to the summary; written as JSON if it ends with .json (needs the
+    /// serde feature), CSV otherwise
+    #[clap(short, long, default_value = "result.csv")]
+    summary: PathBuf,
+}
+
+fn main() {
+    let cli = Cli::parse();
+    let jobs = cli.jobs.unwrap_or_else(default_jobs);
+    let timeout = Duration::from_secs_f64(cli.timeout);
+    let json = cli.summary.extension().is_some_and(|e| e == "json");
+    if json && cfg!(not(feature = "serde")) {
+        eprintln!("JSON summaries need the serde feature (--features serde); use a .csv summary");
+        std::process::exit(1)
+    }
+    let results = run_all(&cli.solver, &cli.in_dir, &cli.out_dir, timeout, jobs).unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        std::process::exit(1)
+    });
+    let mut w = std::io::BufWriter::new(std::fs::File::create(&cli.summary).unwrap());
+    if json {
+        #[cfg(feature = "serde")]
+        write_json(&mut w, &results).unwrap();
+    } else {
+        write_csv(&mut w, &results).unwrap();
+    }
+    let total: i64 = results.iter().map(|r| r.score).sum();
+    let errors = results.iter().filter(|r| !r.error.is_empty()).count();
+    let max_time = results.iter().map(|r| r.time_ms).max().unwrap_or(0);
+    println!("Total = {}", total);
+    println!("Errors = {}", errors);
+    println!("Max time = {} ms", max_time);
+}
@@ -0,0 +1,53 @@
+#![allow(non_snake_case)]
+
+use tools::*;
+
+fn main() {
+    if std::env::args().len() != 2 && std::env::args().len() != 3 {
+        eprintln!(
+            "Usage: {} <input> [<output>]",
+            std::env::args().next().unwrap()
+        );
+        return;
+    }
+    let in_file = std::env::args().nth(1).unwrap();
+    let input = std::fs::read_to_string(&in_file).unwrap_or_else(|_| {
+        eprintln!("no such file: {}", in_file);
+        std::process::exit(1)
+    });
+    let input = parse_input(&input).unwrap_or_else(|err| {
+        eprintln!("{}: {}", in_file, err);
+        std::process::exit(1)
+    });
+    let lb = compute_lower_bound(&input);
+    if lb.reachable < input.N * input.N {
+        println!("Unreachable cells = {}", input.N * input.N - lb.reachable);
+    }
+    println!("Coverage bound = {}", lb.coverage);
+    println!("Reach bound = {}", lb.reach);
+    println!("Diameter bound = {}", lb.diameter);
+    println!("Turns >= {}", lb.turns());
+    println!("Score <= {}", lb.score(&input));
+    if let Some(out_file) = std::env::args().nth(2) {
+        let output = std::fs::read_to_string(&out_file).unwrap_or_else(|_| {
+            eprintln!("no such file: {}", out_file);
+            std::process::exit(1)
+        });
+        let score = match parse_output(&input, &output) {
+            Ok(out) => {
+                println!("Turns = {}", out.actions.len());
+                let (score, err) = compute_score(&input, &out);
+                if let Some(err) = err {
+                    println!("{}", err);
+                }
+                score
+            }
+            Err(err) => {
+                println!("{}", err);
+                0
+            }
+        };
+        println!("Score = {}", score);
+        println!("Gap = {}", lb.score(&input) - score);
+    }
+}
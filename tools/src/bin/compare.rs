@@ -0,0 +1,127 @@
+#![allow(non_snake_case)]
+
+use clap::Parser;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tools::batch::*;
+
+#[derive(Parser, Debug)]
+struct Cli {
+    /// Baseline: a CSV summary written by `batch` (or JSON, with the serde
+    /// feature), or a solver executable
+    a: PathBuf,
+    /// Candidate: a CSV summary written by `batch` (or JSON, with the serde
+    /// feature), or a solver executable
+    b: PathBuf,
+    /// Path to input directory (used when running solvers)
+    #[clap(short = 'i', long = "in", default_value = "in")]
+    in_dir: PathBuf,
+    /// Path to output directory (used when running solvers)
+    #[clap(short = 'o', long = "out", default_value = "out_compare")]
+    out_dir: PathBuf,
+    /// Number of cases to run in parallel (default: number of CPUs)
+    #[clap(short, long)]
+    jobs: Option<usize>,
+    /// Time limit per case in seconds
+    #[clap(short, long, default_value_t = 10.0)]
+    timeout: f64,
+    /// Number of largest regressions to show
+    #[clap(long, default_value_t = 10)]
+    top: usize,
+}
+
+/// Whether `path` can be run as a solver.
+fn is_executable(path: &Path) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        path.metadata()
+            .is_ok_and(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+    }
+    #[cfg(not(unix))]
+    {
+        path.is_file()
+    }
+}
+
+fn load(cli: &Cli, path: &Path, name: &str) -> Vec<CaseResult> {
+    if !path.exists() {
+        eprintln!("no such file: {}", path.display());
+        std::process::exit(1)
+    }
+    let ext = path.extension().and_then(|e| e.to_str());
+    if let Some(ext @ ("csv" | "json")) = ext {
+        let f = std::fs::read_to_string(path).unwrap_or_else(|err| {
+            eprintln!("{}: {}", path.display(), err);
+            std::process::exit(1)
+        });
+        let results = match ext {
+            "csv" => read_csv(&f),
+            #[cfg(feature = "serde")]
+            _ => read_json(&f),
+            #[cfg(not(feature = "serde"))]
+            _ => Err(
+                "JSON summaries need the serde feature (--features serde); use a .csv summary"
+                    .to_owned(),
+            ),
+        };
+        return results.unwrap_or_else(|err| {
+            eprintln!("{}: {}", path.display(), err);
+            std::process::exit(1)
+        });
+    }
+    if !is_executable(path) {
+        eprintln!(
+            "{}: expected a .csv or .json summary written by batch, or a solver executable",
+            path.display()
+        );
+        std::process::exit(1)
+    }
+    let jobs = cli.jobs.unwrap_or_else(default_jobs);
+    let timeout = Duration::from_secs_f64(cli.timeout);
+    // A bare file name would otherwise be looked up in PATH.
+    let solver = path.canonicalize().unwrap_or_else(|_| path.to_owned());
+    let results = run_all(&solver, &cli.in_dir, &cli.out_dir.join(name), timeout, jobs)
+        .unwrap_or_else(|err| {
+            eprintln!("{}", err);
+            std::process::exit(1)
+        });
+    // A solver that cannot be started would otherwise score 0 on every case.
+    if let Some(r) = results
+        .iter()
+        .find(|r| r.error.starts_with("cannot run solver"))
+    {
+        eprintln!("{}: {}: {}", path.display(), r.name, r.error);
+        std::process::exit(1)
+    }
+    results
+}
+
+fn main() {
+    let cli = Cli::parse();
+    let a = load(&cli, &cli.a, "a");
+    let b = load(&cli, &cli.b, "b");
+    let c = compare(&a, &b);
+    println!("Cases = {}", c.pairs.len());
+    println!(
+        "Total: A = {}, B = {}",
+        c.pairs.iter().map(|p| p.1).sum::<i64>(),
+        c.pairs.iter().map(|p| p.2).sum::<i64>()
+    );
+    println!("Mean delta (B - A) = {:.3}", c.mean_delta);
+    println!("Median delta (B - A) = {:.1}", c.median_delta);
+    println!("Win / Loss / Tie = {} / {} / {}", c.wins, c.losses, c.ties);
+    println!("Wilcoxon signed-rank p = {:.4}", c.p_value);
+    let mut regressions = c
+        .pairs
+        .iter()
+        .filter(|p| p.2 < p.1)
+        .collect::<Vec<_>>();
+    regressions.sort_by_key(|p| p.2 - p.1);
+    if !regressions.is_empty() {
+        println!("Largest regressions:");
+        for (name, a, b) in regressions.into_iter().take(cli.top) {
+            println!("  {}: {} -> {} ({:+})", name, a, b, b - a);
+        }
+    }
+}
@@ -0,0 +1,133 @@
+#![allow(non_snake_case)]
+
+use clap::{Parser, Subcommand};
+use tools::*;
+
+#[derive(Parser, Debug)]
+struct Cli {
+    #[clap(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Convert an input file to JSON (or back with --from-json)
+    Input {
+        /// Path to input file
+        input: String,
+        #[clap(long)]
+        from_json: bool,
+    },
+    /// Convert an output file to JSON (or back with --from-json).
+    /// Commands are stored as indices into UDLRS.
+    Output {
+        /// Path to input file (always in the text format)
+        input: String,
+        /// Path to output file
+        output: String,
+        #[clap(long)]
+        from_json: bool,
+    },
+    /// Dump the simulation state after some turns as JSON
+    State {
+        /// Path to input file
+        input: String,
+        /// Path to output file
+        output: String,
+        /// Number of turns to simulate (default: all)
+        #[clap(short, long)]
+        turn: Option<usize>,
+    },
+}
+
+fn read_file(path: &str) -> String {
+    std::fs::read_to_string(path).unwrap_or_else(|_| {
+        eprintln!("no such file: {}", path);
+        std::process::exit(1)
+    })
+}
+
+fn from_json<T: serde::de::DeserializeOwned>(path: &str) -> T {
+    serde_json::from_str(&read_file(path)).unwrap_or_else(|err| {
+        eprintln!("{}: {}", path, err);
+        std::process::exit(1)
+    })
+}
+
+fn read_input(path: &str) -> Input {
+    parse_input(&read_file(path)).unwrap_or_else(|err| {
+        eprintln!("{}: {}", path, err);
+        std::process::exit(1)
+    })
+}
+
+/// Checks a decoded input by reading its text form back. Trailing rows would
+/// be ignored by the parser, so the text must also be reproduced exactly.
+fn check_input(path: &str, input: Input) -> Input {
+    let text = input.to_string();
+    match parse_input(&text) {
+        Ok(parsed) if parsed.to_string() == text => parsed,
+        Ok(_) => {
+            eprintln!("{}: more robots or wall rows than N and M allow", path);
+            std::process::exit(1)
+        }
+        Err(err) => {
+            eprintln!("{}: invalid input: {}", path, err);
+            std::process::exit(1)
+        }
+    }
+}
+
+fn read_output(input: &Input, path: &str) -> Output {
+    parse_output(input, &read_file(path)).unwrap_or_else(|err| {
+        eprintln!("{}: {}", path, err);
+        std::process::exit(1)
+    })
+}
+
+fn main() {
+    let cli = Cli::parse();
+    match cli.command {
+        Command::Input { input, from_json: true } => {
+            let input = check_input(&input, from_json(&input));
+            print!("{}", input);
+        }
+        Command::Input { input, from_json: false } => {
+            let input = read_input(&input);
+            println!("{}", serde_json::to_string(&input).unwrap());
+        }
+        Command::Output {
+            input,
+            output,
+            from_json: true,
+        } => {
+            let input = read_input(&input);
+            let out: Output = from_json(&output);
+            if let (_, Some(err)) = compute_score(&input, &out) {
+                eprintln!("{}: {}", output, err);
+                std::process::exit(1)
+            }
+            print!("{}", out);
+        }
+        Command::Output {
+            input,
+            output,
+            from_json: false,
+        } => {
+            let input = read_input(&input);
+            let out = read_output(&input, &output);
+            println!("{}", serde_json::to_string(&out).unwrap());
+        }
+        Command::State {
+            input,
+            output,
+            turn,
+        } => {
+            let input = read_input(&input);
+            let out = read_output(&input, &output);
+            let turn = turn.unwrap_or(out.actions.len()).min(out.actions.len());
+            let (_, _, state) = compute_score_details(&input, &out, turn);
+            println!("{}", serde_json::to_string(&state).unwrap());
+        }
+    }
+}
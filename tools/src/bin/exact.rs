@@ -0,0 +1,34 @@
+#![allow(non_snake_case)]
+
+use clap::Parser;
+use tools::*;
+
+/// Computes an optimal output for a tiny input. With M, K <= 3, solves N = 4
+/// in under a second, N = 5 in seconds and N = 6 in one to a few minutes.
+#[derive(Parser, Debug)]
+struct Cli {
+    /// Path to input file
+    input: String,
+    /// Give up when a search would store more than this many states
+    #[clap(long, default_value_t = 50_000_000)]
+    max_states: usize,
+}
+
+fn main() {
+    let cli = Cli::parse();
+    let input = std::fs::read_to_string(&cli.input).unwrap_or_else(|_| {
+        eprintln!("no such file: {}", cli.input);
+        std::process::exit(1)
+    });
+    let input = parse_input(&input).unwrap_or_else(|err| {
+        eprintln!("{}: {}", cli.input, err);
+        std::process::exit(1)
+    });
+    let out = exact::solve_exact(&input, cli.max_states).unwrap_or_else(|| {
+        eprintln!("too large to solve exactly");
+        std::process::exit(1)
+    });
+    print!("{}", out);
+    let (score, _) = compute_score(&input, &out);
+    eprintln!("Score = {}", score);
+}
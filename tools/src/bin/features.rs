@@ -0,0 +1,47 @@
+#![allow(non_snake_case)]
+
+use clap::Parser;
+use std::path::PathBuf;
+use tools::features::*;
+use tools::*;
+
+#[derive(Parser, Debug)]
+struct Cli {
+    /// Input files or directories of input files
+    #[clap(default_value = "in")]
+    inputs: Vec<PathBuf>,
+}
+
+fn main() {
+    let cli = Cli::parse();
+    let mut files = vec![];
+    for path in cli.inputs {
+        if path.is_dir() {
+            let mut entries = std::fs::read_dir(&path)
+                .unwrap()
+                .map(|e| e.unwrap().path())
+                .filter(|p| p.is_file())
+                .collect::<Vec<_>>();
+            entries.sort();
+            files.extend(entries);
+        } else {
+            files.push(path);
+        }
+    }
+    println!("file,{}", Features::CSV_HEADER);
+    for file in files {
+        let input = std::fs::read_to_string(&file).unwrap_or_else(|_| {
+            eprintln!("no such file: {}", file.display());
+            std::process::exit(1)
+        });
+        let input = parse_input(&input).unwrap_or_else(|err| {
+            eprintln!("{}: {}", file.display(), err);
+            std::process::exit(1)
+        });
+        println!(
+            "{},{}",
+            file.file_name().unwrap().to_string_lossy(),
+            compute_features(&input).to_csv()
+        );
+    }
+}
@@ -14,10 +14,45 @@ struct Cli {
     #[clap(short, long)]
     /// Print input details in csv format
     verbose: bool,
+    /// Grid size (default: 30)
+    #[clap(long = "N")]
+    N: Option<usize>,
+    /// Number of robots (default: 10)
+    #[clap(long = "M")]
+    M: Option<usize>,
+    /// Number of buttons (default: 10)
+    #[clap(long = "K")]
+    K: Option<usize>,
+    /// Number of walls (default: 5)
+    #[clap(long)]
+    walls: Option<usize>,
+    /// Minimum wall length (default: 10)
+    #[clap(long)]
+    min_len: Option<usize>,
+    /// Maximum wall length (default: 20)
+    #[clap(long)]
+    max_len: Option<usize>,
+    /// Minimum distance between parallel walls (default: 5)
+    #[clap(long)]
+    spacing: Option<usize>,
 }
 
 fn main() {
     let cli = Cli::parse();
+    let default = GenConfig::default();
+    let config = GenConfig {
+        N: cli.N.unwrap_or(default.N),
+        M: cli.M.unwrap_or(default.M),
+        K: cli.K.unwrap_or(default.K),
+        walls: cli.walls.unwrap_or(default.walls),
+        min_len: cli.min_len.unwrap_or(default.min_len),
+        max_len: cli.max_len.unwrap_or(default.max_len),
+        spacing: cli.spacing.unwrap_or(default.spacing),
+    };
+    if let Err(err) = config.validate() {
+        eprintln!("{}", err);
+        std::process::exit(1)
+    }
     if !std::path::Path::new(&cli.dir).exists() {
         std::fs::create_dir(&cli.dir).unwrap();
     }
@@ -28,21 +63,29 @@ fn main() {
     let f = std::io::BufReader::new(f);
     let mut id = 0;
     if cli.verbose {
-        println!("file,seed");
+        println!("file,seed,{}", features::Features::CSV_HEADER);
     }
     for line in f.lines() {
         let line = line.unwrap();
         let line = line.trim();
-        if line.len() == 0 {
+        if line.is_empty() {
             continue;
         }
         let seed = line.parse::<u64>().unwrap_or_else(|_| {
             eprintln!("parse failed: {}", line);
             std::process::exit(1)
         });
-        let input = gen(seed);
+        let input = gen_with(seed, &config).unwrap_or_else(|err| {
+            eprintln!("seed {}: {}", seed, err);
+            std::process::exit(1)
+        });
         if cli.verbose {
-            println!("{:04},{}", id, seed);
+            println!(
+                "{:04},{},{}",
+                id,
+                seed,
+                features::compute_features(&input).to_csv()
+            );
         }
         let mut w = std::io::BufWriter::new(std::fs::File::create(cli.dir.join(format!("{:04}.txt", id))).unwrap());
         write!(w, "{}", input).unwrap();
@@ -0,0 +1,221 @@
+#![allow(non_snake_case)]
+
+use clap::Parser;
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+use tools::*;
+
+#[derive(Parser, Debug)]
+struct Cli {
+    /// Path to input directory
+    #[clap(short = 'i', long = "in", default_value = "in")]
+    in_dir: PathBuf,
+    /// Path to output directory
+    #[clap(short = 'o', long = "out", default_value = "out")]
+    out_dir: PathBuf,
+    /// Port to listen on (localhost only)
+    #[clap(short, long, default_value_t = 8000)]
+    port: u16,
+    /// Draw robot paths and moves blocked by walls
+    #[clap(long)]
+    trajectory: bool,
+    /// Color cells by the turn they were first visited
+    #[clap(long)]
+    heatmap: bool,
+}
+
+/// Rendered pages by case name, with the stamp they were rendered for.
+type Cache = HashMap<String, (String, String)>;
+
+/// Changes whenever the input or output file of a case is modified.
+fn stamp(cli: &Cli, name: &str) -> String {
+    let mtime = |path: PathBuf| {
+        std::fs::metadata(path)
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+            .map_or("-".to_owned(), |d| d.as_nanos().to_string())
+    };
+    format!(
+        "{}:{}",
+        mtime(cli.in_dir.join(name)),
+        mtime(cli.out_dir.join(name))
+    )
+}
+
+/// File names in `dir`, sorted.
+fn list(dir: &Path) -> Vec<String> {
+    let mut names = vec![];
+    if let Ok(entries) = std::fs::read_dir(dir) {
+        for e in entries.flatten() {
+            if e.path().is_file() {
+                names.push(e.file_name().to_string_lossy().into_owned());
+            }
+        }
+    }
+    names.sort();
+    names
+}
+
+/// Accepts only plain file names so that requests cannot leave the directories.
+fn case_name(name: &str) -> Option<&str> {
+    let ok = !name.is_empty()
+        && !name.starts_with('.')
+        && !name.contains(['/', '\\'])
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "._-".contains(c));
+    ok.then_some(name)
+}
+
+/// Parses and scores a case. The error is the reason it could not be scored.
+fn load(cli: &Cli, name: &str) -> Result<(Input, Output), String> {
+    let input = std::fs::read_to_string(cli.in_dir.join(name))
+        .map_err(|_| format!("no such file: {}", cli.in_dir.join(name).display()))?;
+    let input = parse_input(&input).map_err(|err| format!("input: {}", err))?;
+    let output = std::fs::read_to_string(cli.out_dir.join(name))
+        .map_err(|_| format!("no such file: {}", cli.out_dir.join(name).display()))?;
+    let out = parse_output(&input, &output).map_err(|err| err.to_string())?;
+    Ok((input, out))
+}
+
+fn index(cli: &Cli) -> String {
+    let mut rows = vec![];
+    let mut total = 0;
+    for name in list(&cli.in_dir) {
+        let score = match load(cli, &name) {
+            Ok((input, out)) => match compute_score(&input, &out) {
+                (score, None) => {
+                    total += score;
+                    score.to_string()
+                }
+                (_, Some(err)) => format!("0 ({})", err),
+            },
+            Err(err) => format!("- ({})", err),
+        };
+        rows.push(format!(
+            "<tr><td><a href=\"/vis/{0}\">{0}</a></td><td>{1}</td></tr>",
+            name, score
+        ));
+    }
+    format!(
+        "<html><head><title>vis</title></head><body>\
+<p>{} cases, total score = {}</p>\
+<table><tr><th>case</th><th>score</th></tr>{}</table></body></html>",
+        rows.len(),
+        total,
+        rows.join("")
+    )
+}
+
+fn render(cli: &Cli, name: &str) -> String {
+    let opts = VisOptions {
+        trajectory: cli.trajectory,
+        heatmap: cli.heatmap,
+    };
+    let (score, err, svg) = match load(cli, name) {
+        Ok((input, out)) => {
+            let (score, err, svg) = vis_animation(&input, &out, &opts);
+            (score, err.map(|e| e.to_string()), svg)
+        }
+        Err(err) => (0, Some(err), String::new()),
+    };
+    let err = err
+        .map(|err| format!("<p style=\"color:red\">{}</p>", err))
+        .unwrap_or_default();
+    // The page reloads itself when the case files change.
+    format!(
+        r#"<html><head><title>{name}</title></head><body>
+<p><a href="/">all cases</a> {name}: Score = {score}</p>{err}{svg}
+<script>
+setInterval(async () => {{
+  const res = await fetch("/stamp/{name}");
+  if (res.ok && (await res.text()) != "{stamp}") location.reload();
+}}, 1000);
+</script>
+</body></html>"#,
+        stamp = stamp(cli, name),
+    )
+}
+
+/// Returns the status line, content type and body for a request path.
+fn route(cli: &Cli, cache: &Mutex<Cache>, path: &str) -> (&'static str, &'static str, String) {
+    const HTML: &str = "text/html; charset=utf-8";
+    if path == "/" {
+        return ("200 OK", HTML, index(cli));
+    }
+    if let Some(name) = path.strip_prefix("/vis/").and_then(case_name) {
+        let stamp = stamp(cli, name);
+        if let Some((s, page)) = cache.lock().unwrap().get(name) {
+            if *s == stamp {
+                return ("200 OK", HTML, page.clone());
+            }
+        }
+        // Rendered without holding the lock so other requests are not blocked.
+        let page = render(cli, name);
+        let mut cache = cache.lock().unwrap();
+        cache.insert(name.to_owned(), (stamp, page.clone()));
+        return ("200 OK", HTML, page);
+    }
+    if let Some(name) = path.strip_prefix("/stamp/").and_then(case_name) {
+        return ("200 OK", "text/plain", stamp(cli, name));
+    }
+    ("404 Not Found", "text/plain", "not found".to_owned())
+}
+
+fn handle(cli: &Cli, cache: &Mutex<Cache>, mut stream: TcpStream) -> std::io::Result<()> {
+    // A client that never finishes its request must not hold a thread forever.
+    stream.set_read_timeout(Some(Duration::from_secs(10)))?;
+    let mut reader = BufReader::new(&stream);
+    let mut request = String::new();
+    reader.read_line(&mut request)?;
+    // Headers are not needed.
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 || line.trim().is_empty() {
+            break;
+        }
+    }
+    let mut words = request.split_whitespace();
+    let (status, content_type, body) = match (words.next(), words.next()) {
+        (Some("GET"), Some(path)) => route(cli, cache, path),
+        _ => (
+            "405 Method Not Allowed",
+            "text/plain",
+            "only GET is supported".to_owned(),
+        ),
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nCache-Control: no-cache\r\nConnection: close\r\n\r\n",
+        status,
+        content_type,
+        body.len()
+    )?;
+    stream.write_all(body.as_bytes())
+}
+
+fn main() {
+    let cli = Cli::parse();
+    let listener = TcpListener::bind(("127.0.0.1", cli.port)).unwrap_or_else(|err| {
+        eprintln!("cannot listen on port {}: {}", cli.port, err);
+        std::process::exit(1)
+    });
+    eprintln!("Serving on http://127.0.0.1:{}/", cli.port);
+    let cache = Mutex::new(Cache::new());
+    // One thread per connection, so a slow client does not block the others.
+    std::thread::scope(|s| {
+        for stream in listener.incoming() {
+            let (cli, cache) = (&cli, &cache);
+            s.spawn(move || {
+                if let Err(err) = stream.and_then(|stream| handle(cli, cache, stream)) {
+                    eprintln!("{}", err);
+                }
+            });
+        }
+    });
+}
@@ -0,0 +1,231 @@
+#![allow(non_snake_case)]
+
+use clap::Parser;
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use crossterm::{cursor, execute, terminal};
+use std::io::{IsTerminal, Write};
+use tools::*;
+
+#[derive(Parser, Debug)]
+struct Cli {
+    /// Path to input file
+    input: String,
+    /// Path to output file
+    output: String,
+    /// Print this turn and exit instead of stepping interactively
+    #[clap(long)]
+    turn: Option<usize>,
+}
+
+const HELP: &str = "←/→ step  ↑/↓ ±10  PgUp/PgDn ±100  Home/End  q quit";
+
+/// Box-drawing character joining wall segments going up, down, left and right.
+fn junction(up: bool, down: bool, left: bool, right: bool) -> char {
+    match (up, down, left, right) {
+        (false, false, false, false) => ' ',
+        (true, true, false, false) => '│',
+        (false, false, true, true) => '─',
+        (true, false, false, false) => '╵',
+        (false, true, false, false) => '╷',
+        (false, false, true, false) => '╴',
+        (false, false, false, true) => '╶',
+        (false, true, false, true) => '┌',
+        (false, true, true, false) => '┐',
+        (true, false, false, true) => '└',
+        (true, false, true, false) => '┘',
+        (true, true, false, true) => '├',
+        (true, true, true, false) => '┤',
+        (false, true, true, true) => '┬',
+        (true, false, true, true) => '┴',
+        (true, true, true, true) => '┼',
+    }
+}
+
+/// ANSI background escape for a `#rrggbb[aa]` color drawn over white.
+fn background(color: &str) -> String {
+    let hex = |k: usize| u8::from_str_radix(&color[1 + 2 * k..3 + 2 * k], 16).unwrap() as f64;
+    let a = if color.len() == 9 {
+        hex(3) / 255.0
+    } else {
+        1.0
+    };
+    let c = |k: usize| (255.0 * (1.0 - a) + hex(k) * a).round() as u8;
+    format!("\x1b[48;2;{};{};{}m", c(0), c(1), c(2))
+}
+
+/// `text` drawn over the background `fill` (empty for the default).
+fn paint(fill: &str, text: &str) -> String {
+    if fill.is_empty() {
+        text.to_owned()
+    } else {
+        format!("{}{}\x1b[0m", fill, text)
+    }
+}
+
+/// The grid after the current turn, one string per terminal row.
+fn frame(input: &Input, sim: &Simulator) -> Vec<String> {
+    let N = input.N;
+    let used = sim.visited();
+    let fill = |i: usize, j: usize| {
+        if used[i][j] == !0 {
+            String::new()
+        } else {
+            background(&robot_color(input, used[i][j]))
+        }
+    };
+    let wall_v = |i: usize, c: usize| c == 0 || c == N || input.wall_v[i][c - 1];
+    let wall_h = |r: usize, j: usize| r == 0 || r == N || input.wall_h[r - 1][j];
+    let mut lines = vec![];
+    for r in 0..=N {
+        // Walls between rows r - 1 and r.
+        let mut line = String::new();
+        for c in 0..=N {
+            line.push(junction(
+                r > 0 && wall_v(r - 1, c),
+                r < N && wall_v(r, c),
+                c > 0 && wall_h(r, c - 1),
+                c < N && wall_h(r, c),
+            ));
+            if c < N {
+                if wall_h(r, c) {
+                    line.push_str("──");
+                } else if fill(r - 1, c) == fill(r, c) {
+                    line.push_str(&paint(&fill(r, c), "  "));
+                } else {
+                    line.push_str("  ");
+                }
+            }
+        }
+        lines.push(line);
+        if r == N {
+            break;
+        }
+        let mut line = String::new();
+        for c in 0..=N {
+            if c > 0 {
+                // Later robots are drawn on top.
+                let robot = sim.positions().iter().rposition(|&p| p == (r, c - 1));
+                if let Some(k) = robot {
+                    line.push_str(&format!("\x1b[1;30;47m{:>2}\x1b[0m", k));
+                } else {
+                    line.push_str(&paint(&fill(r, c - 1), "  "));
+                }
+            }
+            if wall_v(r, c) {
+                line.push('│');
+            } else if fill(r, c - 1) == fill(r, c) {
+                line.push_str(&paint(&fill(r, c), " "));
+            } else {
+                line.push(' ');
+            }
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+fn status(out: &Output, sim: &Simulator, err: &Option<String>) -> String {
+    let t = sim.turn();
+    let mut s = format!(
+        "turn {}/{}  score {}",
+        t,
+        out.actions.len(),
+        sim.score_if_stopped_now()
+    );
+    if t > 0 {
+        let a = out.actions[t - 1];
+        let cmds = out.cs[a].iter().map(|&c| CMD[c]).collect::<String>();
+        s += &format!("  button {} ({})", a, cmds);
+    }
+    if let Some(err) = err {
+        s += &format!("  \x1b[31m{}\x1b[0m", err);
+    }
+    s
+}
+
+/// Steps or undoes until the simulator is at turn `t` or cannot go further.
+fn seek(sim: &mut Simulator, out: &Output, t: usize) {
+    while sim.turn() > t {
+        sim.undo();
+    }
+    while sim.turn() < t.min(out.actions.len()) {
+        if sim.step(out.actions[sim.turn()]).is_err() {
+            break;
+        }
+    }
+}
+
+fn interactive(input: &Input, out: &Output, err: &Option<String>) -> std::io::Result<()> {
+    let mut stdout = std::io::stdout();
+    terminal::enable_raw_mode()?;
+    execute!(stdout, terminal::EnterAlternateScreen, cursor::Hide)?;
+    let mut sim = Simulator::new(input, &out.cs);
+    let T = out.actions.len();
+    let result = (|| loop {
+        execute!(stdout, cursor::MoveTo(0, 0))?;
+        for line in frame(input, &sim) {
+            write!(stdout, "{}\r\n", line)?;
+        }
+        write!(stdout, "{}\x1b[K\r\n{}\x1b[K", status(out, &sim, err), HELP)?;
+        stdout.flush()?;
+        let t = sim.turn();
+        let key = match event::read()? {
+            Event::Key(key) if key.kind != KeyEventKind::Release => key,
+            Event::Resize(..) => {
+                execute!(stdout, terminal::Clear(terminal::ClearType::All))?;
+                continue;
+            }
+            _ => continue,
+        };
+        let target = match key.code {
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => return Ok(()),
+            KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+            KeyCode::Right | KeyCode::Char('l') | KeyCode::Char(' ') => t + 1,
+            KeyCode::Left | KeyCode::Char('h') => t.saturating_sub(1),
+            KeyCode::Down | KeyCode::Char('j') => t + 10,
+            KeyCode::Up | KeyCode::Char('k') => t.saturating_sub(10),
+            KeyCode::PageDown => t + 100,
+            KeyCode::PageUp => t.saturating_sub(100),
+            KeyCode::Home | KeyCode::Char('g') => 0,
+            KeyCode::End | KeyCode::Char('G') => T,
+            _ => continue,
+        };
+        seek(&mut sim, out, target);
+    })();
+    execute!(stdout, cursor::Show, terminal::LeaveAlternateScreen)?;
+    terminal::disable_raw_mode()?;
+    result
+}
+
+fn main() {
+    let cli = Cli::parse();
+    let in_file = &cli.input;
+    let out_file = &cli.output;
+    let input = std::fs::read_to_string(in_file).unwrap_or_else(|_| {
+        eprintln!("no such file: {}", in_file);
+        std::process::exit(1)
+    });
+    let output = std::fs::read_to_string(out_file).unwrap_or_else(|_| {
+        eprintln!("no such file: {}", out_file);
+        std::process::exit(1)
+    });
+    let input = parse_input(&input).unwrap_or_else(|err| {
+        eprintln!("{}: {}", in_file, err);
+        std::process::exit(1)
+    });
+    let out = parse_output(&input, &output).unwrap_or_else(|err| {
+        eprintln!("{}: {}", out_file, err);
+        std::process::exit(1)
+    });
+    let err = compute_score(&input, &out).1.map(|e| e.to_string());
+    if cli.turn.is_none() && std::io::stdout().is_terminal() {
+        interactive(&input, &out, &err).unwrap();
+        return;
+    }
+    let mut sim = Simulator::new(&input, &out.cs);
+    seek(&mut sim, &out, cli.turn.unwrap_or(out.actions.len()));
+    for line in frame(&input, &sim) {
+        println!("{}", line);
+    }
+    println!("{}", status(&out, &sim, &err));
+}
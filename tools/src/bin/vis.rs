@@ -1,34 +1,81 @@
 #![allow(non_snake_case)]
 
+use clap::Parser;
 use tools::*;
 
+#[derive(Parser, Debug)]
+struct Cli {
+    /// Path to input file
+    input: String,
+    /// Path to output file
+    output: String,
+    /// Draw robot paths and moves blocked by walls
+    #[clap(long)]
+    trajectory: bool,
+    /// Color cells by the turn they were first visited
+    #[clap(long)]
+    heatmap: bool,
+    /// Also write the final state as a PNG image to this path
+    #[clap(long)]
+    png: Option<String>,
+    /// Also write an animated GIF of all turns to this path
+    #[clap(long)]
+    gif: Option<String>,
+    /// Width of the grid in PNG/GIF images, in pixels
+    #[clap(long, default_value_t = 600)]
+    size: usize,
+    /// Maximum number of GIF frames; turns are skipped evenly beyond this
+    #[clap(long, default_value_t = 300)]
+    frames: usize,
+}
+
 fn main() {
-    if std::env::args().len() != 3 {
-        eprintln!("Usage: {} <input> <output>", std::env::args().nth(0).unwrap());
-        return;
-    }
-    let in_file = std::env::args().nth(1).unwrap();
-    let out_file = std::env::args().nth(2).unwrap();
-    let input = std::fs::read_to_string(&in_file).unwrap_or_else(|_| {
+    let cli = Cli::parse();
+    let in_file = &cli.input;
+    let out_file = &cli.output;
+    let input = std::fs::read_to_string(in_file).unwrap_or_else(|_| {
         eprintln!("no such file: {}", in_file);
         std::process::exit(1)
     });
-    let output = std::fs::read_to_string(&out_file).unwrap_or_else(|_| {
+    let output = std::fs::read_to_string(out_file).unwrap_or_else(|_| {
         eprintln!("no such file: {}", out_file);
         std::process::exit(1)
     });
-    let input = parse_input(&input);
+    let opts = VisOptions {
+        trajectory: cli.trajectory,
+        heatmap: cli.heatmap,
+    };
+    let input = parse_input(&input).unwrap_or_else(|err| {
+        eprintln!("{}: {}", in_file, err);
+        std::process::exit(1)
+    });
     let out = parse_output(&input, &output);
+    if let Ok(out) = &out {
+        if let Some(path) = &cli.png {
+            let (_, _, png) = raster::vis_png(&input, out, out.actions.len(), &opts, cli.size);
+            std::fs::write(path, png).unwrap();
+        }
+        if let Some(path) = &cli.gif {
+            let (_, _, gif) = raster::vis_gif(&input, out, &opts, cli.size, cli.frames);
+            std::fs::write(path, gif).unwrap();
+        }
+    }
     let (score, err, svg) = match out {
-        Ok(out) => vis_default(&input, &out),
-        Err(err) => (0, err, String::new()),
+        Ok(out) => {
+            let (score, err, svg) = vis_animation(&input, &out, &opts);
+            (score, err.map(|e| e.to_string()), svg)
+        }
+        Err(err) => (0, Some(err.to_string()), String::new()),
     };
-    if err.len() > 0 {
+    if let Some(err) = &err {
         println!("{}", err);
         println!("Score = {}", 0);
     } else {
         println!("Score = {}", score);
     }
-    let vis = format!("<html><body>{}</body></html>", svg);
+    let err = err
+        .map(|err| format!("<p style=\"color:red\">{}</p>", err))
+        .unwrap_or_default();
+    let vis = format!("<html><body>{}{}</body></html>", err, svg);
     std::fs::write("vis.html", &vis).unwrap();
 }
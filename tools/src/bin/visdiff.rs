@@ -0,0 +1,48 @@
+#![allow(non_snake_case)]
+
+use clap::Parser;
+use tools::visdiff::*;
+use tools::*;
+
+#[derive(Parser, Debug)]
+struct Cli {
+    /// Path to input file
+    input: String,
+    /// Path to output file A
+    output_a: String,
+    /// Path to output file B
+    output_b: String,
+}
+
+fn read_file(path: &str) -> String {
+    std::fs::read_to_string(path).unwrap_or_else(|_| {
+        eprintln!("no such file: {}", path);
+        std::process::exit(1)
+    })
+}
+
+fn main() {
+    let cli = Cli::parse();
+    let input = parse_input(&read_file(&cli.input)).unwrap_or_else(|err| {
+        eprintln!("{}: {}", cli.input, err);
+        std::process::exit(1)
+    });
+    let [a, b] = [&cli.output_a, &cli.output_b].map(|path| {
+        parse_output(&input, &read_file(path)).unwrap_or_else(|err| {
+            eprintln!("{}: {}", path, err);
+            std::process::exit(1)
+        })
+    });
+    let (results, html) = vis_diff(&input, &a, &b);
+    for (name, (score, err)) in ["A", "B"].iter().zip(results) {
+        if let Some(err) = err {
+            println!("{}: {}", name, err);
+        }
+        println!("Score {} = {}", name, score);
+    }
+    let (a_first, b_first) = earlier_counts(&input, &a, &b);
+    println!("Cells covered earlier by A = {}", a_first);
+    println!("Cells covered earlier by B = {}", b_first);
+    let vis = format!("<html><body>{}</body></html>", html);
+    std::fs::write("visdiff.html", &vis).unwrap();
+}
@@ -0,0 +1,489 @@
+//! Exact solver for tiny instances.
+//!
+//! Searches jointly over button allocations and action sequences by BFS.
+//! A button's directions are fixed at the moment it is first pressed, and a
+//! state keeps its buttons as a sorted set, so allocations that only differ
+//! by a permutation of buttons or by the order of first presses are explored
+//! once. Duplicate buttons and presses that move no robot are never useful and
+//! are skipped. Once a robot's directions are fixed by the buttons, cells it
+//! can no longer reach quickly make the lower bound used for pruning rise.
+//!
+//! With the default limit of the `exact` binary (50M states, a few GB of
+//! memory) it solves N = 4 with M, K <= 3 in under a second, N = 5 in seconds
+//! and N = 6 in one to a few minutes.
+
+use crate::*;
+use std::collections::HashSet;
+
+/// Bits used by the packed state: visited cells (64), robot positions (6 each),
+/// number of buttons in use (4) and the directions of each button (3 each).
+fn fits(input: &Input) -> bool {
+    input.N * input.N <= 64 && 6 * input.M + 4 + 3 * input.K * input.M <= 64 && input.K < 16
+}
+
+struct Codec {
+    M: usize,
+    K: usize,
+}
+
+impl Codec {
+    fn pack(&self, visited: u64, pos: &[usize], used: usize, alloc: &[usize]) -> u128 {
+        let mut hi = 0u64;
+        let mut shift = 0;
+        for &p in pos {
+            hi |= (p as u64) << shift;
+            shift += 6;
+        }
+        hi |= (used as u64) << shift;
+        shift += 4;
+        for &d in alloc {
+            hi |= (d as u64) << shift;
+            shift += 3;
+        }
+        (hi as u128) << 64 | visited as u128
+    }
+
+    fn unpack(&self, s: u128) -> (u64, Vec<usize>, usize, Vec<usize>) {
+        let visited = s as u64;
+        let mut hi = (s >> 64) as u64;
+        let mut pos = vec![];
+        for _ in 0..self.M {
+            pos.push((hi & 63) as usize);
+            hi >>= 6;
+        }
+        let used = (hi & 15) as usize;
+        hi >>= 4;
+        let mut alloc = vec![];
+        for _ in 0..self.K * self.M {
+            alloc.push((hi & 7) as usize);
+            hi >>= 3;
+        }
+        (visited, pos, used, alloc)
+    }
+}
+
+/// Precomputed moves and distances of an input.
+struct Solver<'a> {
+    input: &'a Input,
+    codec: Codec,
+    all: u64,
+    /// Cell reached from a cell in each direction.
+    next: Vec<Vec<usize>>,
+    /// `dist[mask * 5 + e][p * N * N + c]`: shortest path length from `p` to
+    /// `c` for a robot that may move in the directions of `mask` and up to `e`
+    /// more (`!0` if unreachable).
+    dist: Vec<Vec<usize>>,
+    /// Every direction tuple that is not all 'S'.
+    tuples: Vec<Vec<usize>>,
+    /// Every cell is reachable, so the turn limit can be used for pruning.
+    prune: bool,
+}
+
+/// A BFS limited to a number of turns.
+struct Search {
+    /// Direction tuples pressed to reach the state visiting the most cells.
+    path: Vec<Vec<usize>>,
+    /// The state visits every cell.
+    complete: bool,
+    /// Some state was cut off by the turn limit.
+    cut: bool,
+}
+
+impl<'a> Solver<'a> {
+    fn new(input: &'a Input) -> Self {
+        let N = input.N;
+        let M = input.M;
+        let mut next = mat![0; N * N; 5];
+        for i in 0..N {
+            for j in 0..N {
+                for (d, &(di, dj)) in DIJ.iter().enumerate() {
+                    next[i * N + j][d] = if can_move(input, (i, j), d) {
+                        (i + di) * N + j + dj
+                    } else {
+                        i * N + j
+                    };
+                }
+            }
+        }
+        // Distances using only the directions in a mask, by BFS from each cell.
+        let by_mask = (0..16)
+            .map(|mask: usize| {
+                let mut dist = vec![!0; N * N * N * N];
+                for p in 0..N * N {
+                    let d = &mut dist[p * N * N..(p + 1) * N * N];
+                    d[p] = 0;
+                    let mut que = std::collections::VecDeque::from([p]);
+                    while let Some(c) = que.pop_front() {
+                        for dir in (0..4).filter(|&dir| mask >> dir & 1 != 0) {
+                            let c2 = next[c][dir];
+                            if d[c2] == !0 {
+                                d[c2] = d[c] + 1;
+                                que.push_back(c2);
+                            }
+                        }
+                    }
+                }
+                dist
+            })
+            .collect::<Vec<_>>();
+        let mut dist = vec![];
+        for mask in 0..16usize {
+            for e in 0..5 {
+                let mut d = by_mask[mask].clone();
+                for (more, other) in by_mask.iter().enumerate() {
+                    if more & mask == mask && (more & !mask).count_ones() as usize <= e {
+                        for (x, &y) in d.iter_mut().zip(other) {
+                            x.setmin(y);
+                        }
+                    }
+                }
+                dist.push(d);
+            }
+        }
+        let mut tuples = vec![];
+        for mut x in 0..5usize.pow(M as u32) {
+            let mut t = vec![];
+            for _ in 0..M {
+                t.push(x % 5);
+                x /= 5;
+            }
+            if t.iter().any(|&d| d != 4) {
+                tuples.push(t);
+            }
+        }
+        Solver {
+            input,
+            codec: Codec { M, K: input.K },
+            all: if N * N == 64 {
+                !0
+            } else {
+                (1u64 << (N * N)) - 1
+            },
+            next,
+            prune: calc_dist(input, &input.ps)
+                .iter()
+                .flatten()
+                .all(|&d| d != !0),
+            dist,
+            tuples,
+        }
+    }
+
+    /// Lower bound on the turns needed to visit every remaining cell, or `!0`
+    /// if some cell cannot be visited. A robot can only move in the directions
+    /// its defined buttons give it, plus one more for each undefined button.
+    fn remaining(&self, visited: u64, pos: &[usize], buttons: &[&[usize]]) -> usize {
+        let NN = self.input.N * self.input.N;
+        let more = (self.input.K - buttons.len()).min(4);
+        let tables = (0..self.input.M)
+            .map(|r| {
+                let mask = buttons
+                    .iter()
+                    .filter(|b| b[r] < 4)
+                    .fold(0, |mask, b| mask | 1 << b[r]);
+                (mask, &self.dist[mask * 5 + more])
+            })
+            .collect::<Vec<_>>();
+        let left = (self.all & !visited).count_ones() as usize;
+        let movable = tables.iter().filter(|t| t.0 != 0 || more > 0).count();
+        if left > 0 && movable == 0 {
+            return !0;
+        }
+        let mut turns = left.div_ceil(movable.max(1));
+        let mut rest = self.all & !visited;
+        while rest != 0 {
+            let c = rest.trailing_zeros() as usize;
+            rest &= rest - 1;
+            let d = (0..self.input.M)
+                .map(|r| tables[r].1[pos[r] * NN + c])
+                .min()
+                .unwrap();
+            turns.setmax(d);
+        }
+        turns
+    }
+
+    /// BFS over states reachable within `limit` turns from which every cell
+    /// could still be visited in time. `None` if more than `max_states` states
+    /// are stored.
+    fn search(&self, start: u128, limit: usize, prune: bool, max_states: usize) -> Option<Search> {
+        let M = self.input.M;
+        let K = self.input.K;
+        // (state, parent, pressed tuple)
+        let mut nodes = vec![(start, !0, !0)];
+        let mut seen = HashSet::new();
+        seen.insert(start);
+        let mut best = 0;
+        let mut cut = false;
+        let mut head = 0;
+        let mut level_end = 1;
+        let mut depth = 0;
+        'search: while head < nodes.len() {
+            if head == level_end {
+                depth += 1;
+                level_end = nodes.len();
+            }
+            let (visited, pos, used, alloc) = self.codec.unpack(nodes[head].0);
+            head += 1;
+            if visited == self.all {
+                best = head - 1;
+                break;
+            }
+            if depth == limit {
+                cut = true;
+                continue;
+            }
+            let buttons = alloc[..used * M].chunks(M).collect::<Vec<_>>();
+            for (t, tuple) in self.tuples.iter().enumerate() {
+                let defined = buttons.contains(&&tuple[..]);
+                if !defined && used == K {
+                    continue;
+                }
+                let mut new_pos = pos.clone();
+                let mut new_visited = visited;
+                for r in 0..M {
+                    new_pos[r] = self.next[pos[r]][tuple[r]];
+                    new_visited |= 1 << new_pos[r];
+                }
+                if new_pos == pos {
+                    continue;
+                }
+                // Buttons are stored sorted, so the order in which they were
+                // first pressed does not matter.
+                let mut new_buttons = buttons.clone();
+                if !defined {
+                    new_buttons.push(tuple);
+                    new_buttons.sort();
+                }
+                let rest = || self.remaining(new_visited, &new_pos, &new_buttons);
+                if prune && (depth + 1).saturating_add(rest()) > limit {
+                    cut = true;
+                    continue;
+                }
+                let state = self.codec.pack(
+                    new_visited,
+                    &new_pos,
+                    new_buttons.len(),
+                    &new_buttons.concat(),
+                );
+                if !seen.insert(state) {
+                    continue;
+                }
+                nodes.push((state, head - 1, t));
+                if (state as u64).count_ones() > (nodes[best].0 as u64).count_ones() {
+                    best = nodes.len() - 1;
+                }
+                if nodes.len() > max_states {
+                    return None;
+                }
+                if state as u64 == self.all {
+                    best = nodes.len() - 1;
+                    break 'search;
+                }
+            }
+        }
+        let mut path = vec![];
+        let mut v = best;
+        while nodes[v].1 != !0 {
+            path.push(self.tuples[nodes[v].2].clone());
+            v = nodes[v].1;
+        }
+        path.reverse();
+        Some(Search {
+            path,
+            complete: nodes[best].0 as u64 == self.all,
+            cut,
+        })
+    }
+
+    /// Numbers buttons in order of first press. Unused buttons stay all 'S'.
+    fn output(&self, path: &[Vec<usize>]) -> Output {
+        let mut cs = mat![4; self.input.K; self.input.M];
+        let mut defined: Vec<&Vec<usize>> = vec![];
+        let mut actions = vec![];
+        for tuple in path {
+            let b = match defined.iter().position(|&t| t == tuple) {
+                Some(b) => b,
+                None => {
+                    cs[defined.len()].clone_from(tuple);
+                    defined.push(tuple);
+                    defined.len() - 1
+                }
+            };
+            actions.push(b);
+        }
+        Output { cs, actions }
+    }
+}
+
+/// Computes an optimal output for a tiny input (N * N <= 64 and M, K small).
+///
+/// Runs a BFS with a limit on the number of turns, raising the limit from a
+/// lower bound until some state visits every cell. States that cannot visit
+/// every cell within the limit (too many cells left for M robots, or a cell
+/// too far from every robot with the directions its buttons allow) are
+/// pruned. Returns `None` if the input is too large to encode or a single BFS
+/// would store more than `max_states` states.
+pub fn solve_exact(input: &Input, max_states: usize) -> Option<Output> {
+    if !fits(input) {
+        return None;
+    }
+    let N = input.N;
+    let solver = Solver::new(input);
+    let pos = input.ps.iter().map(|&(i, j)| i * N + j).collect::<Vec<_>>();
+    let mut visited = 0;
+    for &p in &pos {
+        visited |= 1 << p;
+    }
+    let start = solver
+        .codec
+        .pack(visited, &pos, 0, &vec![0; input.K * input.M]);
+    let mut limit = if solver.prune {
+        solver.remaining(visited, &pos, &[]).min(2 * N * N)
+    } else {
+        2 * N * N
+    };
+    loop {
+        // Pruning keeps only states that can still visit every cell, so the
+        // last search, which returns the best partial state, runs without it.
+        let prune = solver.prune && limit < 2 * N * N;
+        let search = solver.search(start, limit, prune, max_states)?;
+        // Without a cut, every reachable state was seen.
+        if search.complete || !search.cut || limit >= 2 * N * N {
+            return Some(solver.output(&search.path));
+        }
+        limit += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand_chacha::ChaCha20Rng;
+
+    fn random_input(rng: &mut ChaCha20Rng, N: usize, M: usize, K: usize) -> Input {
+        let mut ps = vec![];
+        for i in 0..N {
+            for j in 0..N {
+                ps.push((i, j));
+            }
+        }
+        ps.shuffle(rng);
+        ps.truncate(M);
+        Input {
+            N,
+            M,
+            K,
+            ps,
+            wall_v: (0..N)
+                .map(|_| (0..N - 1).map(|_| rng.gen_bool(0.2)).collect())
+                .collect(),
+            wall_h: (0..N - 1)
+                .map(|_| (0..N).map(|_| rng.gen_bool(0.2)).collect())
+                .collect(),
+        }
+    }
+
+    /// Best score by trying every allocation and running a BFS over
+    /// (positions, visited) for each.
+    fn naive(input: &Input) -> i64 {
+        let N = input.N;
+        let M = input.M;
+        let K = input.K;
+        let mut best = 0;
+        for mut x in 0..5usize.pow((K * M) as u32) {
+            let mut cs = mat![0; K; M];
+            for row in cs.iter_mut() {
+                for c in row.iter_mut() {
+                    *c = x % 5;
+                    x /= 5;
+                }
+            }
+            let mut seen = HashSet::new();
+            let mut level = vec![(input.ps.clone(), 0u64)];
+            for &(i, j) in &input.ps {
+                level[0].1 |= 1 << (i * N + j);
+            }
+            for t in 0..=2 * N * N {
+                let mut next = vec![];
+                for (ps, visited) in level {
+                    let score = if visited.count_ones() as usize == N * N {
+                        (3 * N * N - t) as i64
+                    } else {
+                        visited.count_ones() as i64
+                    };
+                    best.setmax(score);
+                    for row in &cs {
+                        let mut ps = ps.clone();
+                        let mut visited = visited;
+                        for r in 0..M {
+                            if can_move(input, ps[r], row[r]) {
+                                ps[r].0 += DIJ[row[r]].0;
+                                ps[r].1 += DIJ[row[r]].1;
+                            }
+                            visited |= 1 << (ps[r].0 * N + ps[r].1);
+                        }
+                        if seen.insert((ps.clone(), visited)) {
+                            next.push((ps, visited));
+                        }
+                    }
+                }
+                level = next;
+            }
+        }
+        best
+    }
+
+    #[test]
+    fn matches_naive_search() {
+        let mut rng = ChaCha20Rng::seed_from_u64(0);
+        for &(N, M, K) in &[(2, 1, 2), (3, 1, 2), (3, 2, 1), (3, 2, 2), (3, 1, 3)] {
+            for _ in 0..3 {
+                let input = random_input(&mut rng, N, M, K);
+                let out = solve_exact(&input, 1_000_000).unwrap();
+                let (score, err) = compute_score(&input, &out);
+                assert!(err.is_none());
+                assert_eq!(score, naive(&input), "{}", input);
+            }
+        }
+    }
+
+    #[test]
+    fn respects_lower_bound() {
+        let mut rng = ChaCha20Rng::seed_from_u64(1);
+        for &(N, M, K) in &[(4, 2, 2), (4, 2, 3), (3, 3, 2), (4, 3, 1)] {
+            let mut input = random_input(&mut rng, N, M, K);
+            input.wall_v = mat![false; N; N - 1];
+            input.wall_h = mat![false; N - 1; N];
+            let out = solve_exact(&input, 10_000_000).unwrap();
+            let (score, err) = compute_score(&input, &out);
+            assert!(err.is_none());
+            assert!(
+                score <= compute_lower_bound(&input).score(&input),
+                "{}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn best_partial_cover() {
+        // One button moves the robot along row 0 only.
+        let input = Input {
+            N: 3,
+            M: 1,
+            K: 1,
+            ps: vec![(0, 0)],
+            wall_v: mat![false; 3; 2],
+            wall_h: mat![false; 2; 3],
+        };
+        let out = solve_exact(&input, 1_000_000).unwrap();
+        assert_eq!(compute_score(&input, &out), (3, None));
+        assert_eq!(naive(&input), 3);
+    }
+
+    #[test]
+    fn rejects_large_input() {
+        assert!(solve_exact(&gen(0), 1000).is_none());
+    }
+}
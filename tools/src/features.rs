@@ -0,0 +1,165 @@
+//! Per-input features for correlating scores with instance difficulty.
+
+use crate::*;
+
+#[derive(Clone, Debug)]
+pub struct Features {
+    /// Number of maximal straight runs of wall.
+    pub wall_segments: usize,
+    /// Number of wall edges between adjacent cells.
+    pub wall_length: usize,
+    /// Largest BFS distance between two cells.
+    pub diameter: usize,
+    /// Mean BFS distance over all ordered pairs of distinct cells.
+    pub avg_distance: f64,
+    /// Mean BFS distance over all pairs of distinct start points.
+    pub start_dispersion: f64,
+    /// Largest BFS distance from a cell to its nearest start point.
+    pub start_radius: usize,
+    /// Number of cells with exactly one open side.
+    pub dead_ends: usize,
+}
+
+impl Features {
+    pub const CSV_HEADER: &'static str =
+        "wall_segments,wall_length,diameter,avg_distance,start_dispersion,start_radius,dead_ends";
+
+    pub fn to_csv(&self) -> String {
+        format!(
+            "{},{},{},{:.3},{:.3},{},{}",
+            self.wall_segments,
+            self.wall_length,
+            self.diameter,
+            self.avg_distance,
+            self.start_dispersion,
+            self.start_radius,
+            self.dead_ends
+        )
+    }
+}
+
+/// Unreachable pairs are ignored in distance-based features.
+pub fn compute_features(input: &Input) -> Features {
+    let N = input.N;
+    let mut wall_segments = 0;
+    let mut wall_length = 0;
+    for j in 0..N - 1 {
+        for i in 0..N {
+            if input.wall_v[i][j] {
+                wall_length += 1;
+                if i == 0 || !input.wall_v[i - 1][j] {
+                    wall_segments += 1;
+                }
+            }
+        }
+    }
+    for i in 0..N - 1 {
+        for j in 0..N {
+            if input.wall_h[i][j] {
+                wall_length += 1;
+                if j == 0 || !input.wall_h[i][j - 1] {
+                    wall_segments += 1;
+                }
+            }
+        }
+    }
+    let mut diameter = 0;
+    let mut sum = 0;
+    let mut pairs = 0;
+    for i in 0..N {
+        for j in 0..N {
+            for &d in calc_dist(input, &[(i, j)]).iter().flatten() {
+                if d != !0 && d > 0 {
+                    diameter.setmax(d);
+                    sum += d;
+                    pairs += 1;
+                }
+            }
+        }
+    }
+    let mut start_sum = 0;
+    let mut start_pairs = 0;
+    for a in 0..input.M {
+        let dist = calc_dist(input, &[input.ps[a]]);
+        for b in a + 1..input.M {
+            let d = dist[input.ps[b].0][input.ps[b].1];
+            if d != !0 {
+                start_sum += d;
+                start_pairs += 1;
+            }
+        }
+    }
+    let start_radius = calc_dist(input, &input.ps)
+        .iter()
+        .flatten()
+        .filter(|&&d| d != !0)
+        .copied()
+        .max()
+        .unwrap_or(0);
+    let mut dead_ends = 0;
+    for i in 0..N {
+        for j in 0..N {
+            if (0..4).filter(|&d| can_move(input, (i, j), d)).count() == 1 {
+                dead_ends += 1;
+            }
+        }
+    }
+    Features {
+        wall_segments,
+        wall_length,
+        diameter,
+        avg_distance: sum as f64 / pairs.max(1) as f64,
+        start_dispersion: start_sum as f64 / start_pairs.max(1) as f64,
+        start_radius,
+        dead_ends,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn open_grid() {
+        let N = 4;
+        let input = Input {
+            N,
+            M: 2,
+            K: 1,
+            ps: vec![(0, 0), (3, 3)],
+            wall_v: mat![false; N; N - 1],
+            wall_h: mat![false; N - 1; N],
+        };
+        let f = compute_features(&input);
+        assert_eq!(f.wall_segments, 0);
+        assert_eq!(f.wall_length, 0);
+        assert_eq!(f.diameter, 6);
+        // Mean Manhattan distance between distinct cells of a 4x4 grid.
+        assert!((f.avg_distance - 2.0 * 5.0 / 4.0 * 16.0 / 15.0).abs() < 1e-9);
+        assert_eq!(f.start_dispersion, 6.0);
+        assert_eq!(f.start_radius, 3);
+        assert_eq!(f.dead_ends, 0);
+    }
+
+    #[test]
+    fn walls_and_dead_ends() {
+        let N = 3;
+        let mut input = Input {
+            N,
+            M: 1,
+            K: 1,
+            ps: vec![(0, 0)],
+            wall_v: mat![false; N; N - 1],
+            wall_h: mat![false; N - 1; N],
+        };
+        // Two vertical walls in column 0 and one horizontal wall.
+        input.wall_v[0][0] = true;
+        input.wall_v[1][0] = true;
+        input.wall_h[0][2] = true;
+        let f = compute_features(&input);
+        assert_eq!(f.wall_segments, 2);
+        assert_eq!(f.wall_length, 3);
+        // (0, 0) can only go down, (0, 2) can only go left.
+        assert_eq!(f.dead_ends, 2);
+    }
+}
@@ -1,10 +1,16 @@
 #![allow(non_snake_case, unused_macros)]
 
-use proconio::{input, marker::Chars};
 use rand::prelude::*;
 use std::ops::RangeBounds;
 use svg::node::element::{Circle, Group, Line, Rectangle, Style, Text, Title};
 
+pub mod batch;
+pub mod exact;
+pub mod features;
+pub mod maze;
+pub mod raster;
+pub mod visdiff;
+
 pub trait SetMinMax {
     fn setmin(&mut self, v: Self) -> bool;
     fn setmax(&mut self, v: Self) -> bool;
@@ -36,6 +42,7 @@ macro_rules! mat {
 }
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Input {
     pub N: usize,
     pub M: usize,
@@ -73,31 +80,136 @@ impl std::fmt::Display for Input {
     }
 }
 
-pub fn parse_input(f: &str) -> Input {
-    let f = proconio::source::once::OnceSource::from(f);
-    input! {
-        from f,
-        N: usize, M: usize, K: usize,
-        ps: [(usize, usize); M],
-        wall_v: [Chars; N],
-        wall_h: [Chars; N - 1],
-    }
-    let wall_v = wall_v
-        .into_iter()
-        .map(|s| s.into_iter().map(|c| c == '1').collect())
-        .collect();
-    let wall_h = wall_h
-        .into_iter()
-        .map(|s| s.into_iter().map(|c| c == '1').collect())
-        .collect();
-    Input {
+/// Why an input or output file was rejected. Positions are 1-based.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub expected: String,
+    /// The offending token (quoted) or "end of file".
+    pub found: String,
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: expected {}, found {}",
+            self.line, self.column, self.expected, self.found
+        )
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Whitespace-separated tokens with their positions.
+struct Tokens<'a> {
+    tokens: Vec<(usize, usize, &'a str)>,
+    next: usize,
+    /// Position right after the last token, reported on unexpected EOF.
+    end: (usize, usize),
+}
+
+impl<'a> Tokens<'a> {
+    fn new(f: &'a str) -> Self {
+        let mut tokens = vec![];
+        let mut end = (1, 1);
+        for (i, line) in f.lines().enumerate() {
+            for token in line.split_whitespace() {
+                let offset = token.as_ptr() as usize - line.as_ptr() as usize;
+                let column = line[..offset].chars().count() + 1;
+                tokens.push((i + 1, column, token));
+                end = (i + 1, column + token.chars().count());
+            }
+        }
+        Tokens {
+            tokens,
+            next: 0,
+            end,
+        }
+    }
+
+    fn error(pos: (usize, usize), expected: &str, found: String) -> ParseError {
+        ParseError {
+            line: pos.0,
+            column: pos.1,
+            expected: expected.to_owned(),
+            found,
+        }
+    }
+
+    fn next(&mut self, expected: &str) -> Result<(usize, usize, &'a str), ParseError> {
+        match self.tokens.get(self.next) {
+            Some(&token) => {
+                self.next += 1;
+                Ok(token)
+            }
+            None => Err(Self::error(self.end, expected, "end of file".to_owned())),
+        }
+    }
+
+    fn read<T: PartialOrd + std::str::FromStr, R: RangeBounds<T>>(
+        &mut self,
+        expected: &str,
+        range: R,
+    ) -> Result<T, ParseError> {
+        let (line, column, token) = self.next(expected)?;
+        match token.parse::<T>() {
+            Ok(v) if range.contains(&v) => Ok(v),
+            _ => Err(Self::error(
+                (line, column),
+                expected,
+                format!("{:?}", token),
+            )),
+        }
+    }
+
+    /// Reads a row of `len` '0'/'1' characters.
+    fn read_walls(&mut self, expected: &str, len: usize) -> Result<Vec<bool>, ParseError> {
+        let (line, column, token) = self.next(expected)?;
+        if token.len() != len || token.chars().any(|c| c != '0' && c != '1') {
+            return Err(Self::error(
+                (line, column),
+                expected,
+                format!("{:?}", token),
+            ));
+        }
+        Ok(token.chars().map(|c| c == '1').collect())
+    }
+}
+
+pub fn parse_input(f: &str) -> Result<Input, ParseError> {
+    let mut f = Tokens::new(f);
+    let N = f.read("N (integer >= 2)", 2..)?;
+    let M = f.read(&format!("M (integer in 1..={})", N * N), 1..=N * N)?;
+    let K = f.read("K (integer >= 1)", 1..)?;
+    let mut ps = vec![];
+    for k in 0..M {
+        let i = f.read(&format!("row of robot {} (integer in 0..{})", k, N), 0..N)?;
+        let j = f.read(
+            &format!("column of robot {} (integer in 0..{})", k, N),
+            0..N,
+        )?;
+        ps.push((i, j));
+    }
+    let mut wall_v = vec![];
+    for i in 0..N {
+        let expected = format!("row {} of vertical walls ({} characters of 0/1)", i, N - 1);
+        wall_v.push(f.read_walls(&expected, N - 1)?);
+    }
+    let mut wall_h = vec![];
+    for i in 0..N - 1 {
+        let expected = format!("row {} of horizontal walls ({} characters of 0/1)", i, N);
+        wall_h.push(f.read_walls(&expected, N)?);
+    }
+    Ok(Input {
         N,
         M,
         K,
         ps,
         wall_v,
         wall_h,
-    }
+    })
 }
 
 pub fn read<T: Copy + PartialOrd + std::fmt::Display + std::str::FromStr, R: RangeBounds<T>>(
@@ -122,40 +234,140 @@ pub fn read<T: Copy + PartialOrd + std::fmt::Display + std::str::FromStr, R: Ran
 pub const CMD: [char; 5] = ['U', 'D', 'L', 'R', 'S'];
 pub const DIJ: [(usize, usize); 5] = [(!0, 0), (1, 0), (0, !0), (0, 1), (0, 0)];
 
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Output {
     pub cs: Vec<Vec<usize>>,
     pub actions: Vec<usize>,
 }
 
-pub fn parse_output(input: &Input, f: &str) -> Result<Output, String> {
-    let mut f = f.split_whitespace();
+/// Commands outside `CMD` are written as `?`, which `parse_output` rejects.
+impl std::fmt::Display for Output {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for row in &self.cs {
+            writeln!(
+                f,
+                "{}",
+                row.iter()
+                    .map(|&c| CMD.get(c).map_or('?', |&c| c).to_string())
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            )?;
+        }
+        for a in &self.actions {
+            writeln!(f, "{}", a)?;
+        }
+        Ok(())
+    }
+}
+
+pub fn parse_output(input: &Input, f: &str) -> Result<Output, ParseError> {
+    let mut f = Tokens::new(f);
     let mut cs = mat![0; input.K; input.M];
-    for i in 0..input.K {
-        for j in 0..input.M {
-            let c = read(f.next(), 'A'..='Z')?;
-            let c = CMD
+    for (b, row) in cs.iter_mut().enumerate() {
+        for (k, cell) in row.iter_mut().enumerate() {
+            let expected = format!("command of button {} for robot {} (U, D, L, R or S)", b, k);
+            let (line, column, token) = f.next(&expected)?;
+            *cell = CMD
                 .iter()
-                .position(|&x| x == c)
-                .ok_or_else(|| format!("Invalid command: {}", c))?;
-            cs[i][j] = c;
+                .position(|&x| token == x.to_string())
+                .ok_or_else(|| Tokens::error((line, column), &expected, format!("{:?}", token)))?;
         }
     }
     let mut actions = vec![];
-    for a in f {
-        let a = read(Some(a), 0..input.K)?;
-        actions.push(a);
-        if actions.len() > 2 * input.N * input.N {
-            return Err(format!("Too many actions"));
+    let limit = 2 * input.N * input.N;
+    while f.next < f.tokens.len() {
+        if actions.len() == limit {
+            let (line, column, token) = f.tokens[f.next];
+            let expected = format!("end of file (at most {} actions)", limit);
+            return Err(Tokens::error(
+                (line, column),
+                &expected,
+                format!("{:?}", token),
+            ));
         }
+        let expected = format!("button (integer in 0..{})", input.K);
+        actions.push(f.read(&expected, 0..input.K)?);
     }
     Ok(Output { cs, actions })
 }
 
+/// Parameters of the instance distribution. `Default` is the official one.
+#[derive(Clone, Debug)]
+pub struct GenConfig {
+    pub N: usize,
+    pub M: usize,
+    pub K: usize,
+    /// Number of walls.
+    pub walls: usize,
+    /// Wall lengths are drawn uniformly from `min_len..=max_len`.
+    pub min_len: usize,
+    pub max_len: usize,
+    /// Parallel walls are at least this far apart, and walls start at least
+    /// this far from the border.
+    pub spacing: usize,
+}
+
+impl Default for GenConfig {
+    fn default() -> Self {
+        GenConfig {
+            N: 30,
+            M: 10,
+            K: 10,
+            walls: 5,
+            min_len: 10,
+            max_len: 20,
+            spacing: 5,
+        }
+    }
+}
+
+impl GenConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.N < 2 {
+            return Err(format!("N must be at least 2: {}", self.N));
+        }
+        if self.M == 0 || self.M > self.N * self.N {
+            return Err(format!("M must be in 1..={}: {}", self.N * self.N, self.M));
+        }
+        if self.K == 0 {
+            return Err("K must be at least 1".to_owned());
+        }
+        if self.min_len > self.max_len {
+            return Err(format!(
+                "Empty wall length range: {}..={}",
+                self.min_len, self.max_len
+            ));
+        }
+        if self.walls > 0 && (self.spacing == 0 || 2 * self.spacing > self.N) {
+            return Err(format!(
+                "spacing must be in 1..={}: {}",
+                self.N / 2,
+                self.spacing
+            ));
+        }
+        Ok(())
+    }
+}
+
 pub fn gen(seed: u64) -> Input {
+    gen_with(seed, &GenConfig::default()).unwrap()
+}
+
+/// Number of mazes `gen_with` draws before giving up on connecting one.
+const GEN_ATTEMPTS: usize = 1000;
+
+/// Generates an input from a custom distribution. Walls that do not fit are
+/// dropped, and walls are redrawn until the maze is connected. Fails if the
+/// config is invalid or no connected maze is found in `GEN_ATTEMPTS` draws,
+/// e.g. when every wall reaches the border and two of them cut off a corner.
+pub fn gen_with(seed: u64, config: &GenConfig) -> Result<Input, String> {
+    config.validate()?;
     let mut rng = rand_chacha::ChaCha20Rng::seed_from_u64(seed);
-    let N = 30;
-    let M = 10;
-    let K = 10;
+    let N = config.N;
+    let M = config.M;
+    let K = config.K;
+    let s = config.spacing;
     let mut ps = vec![];
     for i in 0..N {
         for j in 0..N {
@@ -166,25 +378,36 @@ pub fn gen(seed: u64) -> Input {
     ps.truncate(M);
     let mut wall_v;
     let mut wall_h;
+    let mut attempts = 0;
     loop {
+        attempts += 1;
+        if attempts > GEN_ATTEMPTS {
+            return Err(format!(
+                "No connected maze in {} attempts; use fewer or shorter walls",
+                GEN_ATTEMPTS
+            ));
+        }
         wall_v = mat![false; N; N - 1];
         wall_h = mat![false; N - 1; N];
         let mut used_v = vec![false; N - 1];
         let mut used_h = vec![false; N - 1];
-        for _ in 0..5 {
+        for _ in 0..config.walls {
+            if used_v[s - 1..N - s].iter().all(|&u| u) && used_h[s - 1..N - s].iter().all(|&u| u) {
+                break;
+            }
             loop {
                 let dir = rng.gen_range(0i32..4) as usize;
-                let l = rng.gen_range(10i32..=20) as usize;
+                let l = rng.gen_range(config.min_len as i32..=config.max_len as i32) as usize;
                 if dir / 2 == 0 {
                     // vertical
-                    let i = rng.gen_range(5..=N as i32 - 5) as usize;
-                    let j = rng.gen_range(4..=N as i32 - 6) as usize;
+                    let i = rng.gen_range(s as i32..=(N - s) as i32) as usize;
+                    let j = rng.gen_range(s as i32 - 1..=(N - 1 - s) as i32) as usize;
                     if used_v[j] {
                         continue;
                     }
-                    for k in 0..9 {
-                        if j - 4 + k < N - 1 {
-                            used_v[j - 4 + k] = true;
+                    for k in 0..2 * s - 1 {
+                        if j + 1 - s + k < N - 1 {
+                            used_v[j + 1 - s + k] = true;
                         }
                     }
                     for k in 0..l {
@@ -195,14 +418,14 @@ pub fn gen(seed: u64) -> Input {
                     }
                 } else {
                     // horizontal
-                    let i = rng.gen_range(4..=N as i32 - 6) as usize;
-                    let j = rng.gen_range(5..=N as i32 - 5) as usize;
+                    let i = rng.gen_range(s as i32 - 1..=(N - 1 - s) as i32) as usize;
+                    let j = rng.gen_range(s as i32..=(N - s) as i32) as usize;
                     if used_h[i] {
                         continue;
                     }
-                    for k in 0..9 {
-                        if i - 4 + k < N - 1 {
-                            used_h[i - 4 + k] = true;
+                    for k in 0..2 * s - 1 {
+                        if i + 1 - s + k < N - 1 {
+                            used_h[i + 1 - s + k] = true;
                         }
                     }
                     for k in 0..l {
@@ -221,8 +444,7 @@ pub fn gen(seed: u64) -> Input {
         let mut num = 0;
         while let Some((i, j)) = stack.pop() {
             num += 1;
-            for d in 0..4 {
-                let (di, dj) = DIJ[d];
+            for &(di, dj) in &DIJ[..4] {
                 let (i2, j2) = (i + di, j + dj);
                 if i2 >= N || j2 >= N || visited[i2][j2] {
                     continue;
@@ -244,28 +466,142 @@ pub fn gen(seed: u64) -> Input {
             break;
         }
     }
-    Input {
+    Ok(Input {
         N,
         M,
         K,
         ps,
         wall_v,
         wall_h,
+    })
+}
+
+/// Reason an output is rejected by the scorer.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ScoreError {
+    /// `cs` is not a K x M table. `found` is (number of rows, length of the
+    /// first row of wrong length).
+    WrongShape {
+        expected: (usize, usize),
+        found: (usize, usize),
+    },
+    /// `cs[button][robot]` is not one of `CMD`.
+    InvalidCommand {
+        button: usize,
+        robot: usize,
+        command: usize,
+    },
+    /// The action at `turn` is not a button.
+    InvalidAction { turn: usize, button: usize },
+    /// The output has more than `limit` actions.
+    TooManyActions { turns: usize, limit: usize },
+}
+
+impl std::fmt::Display for ScoreError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            ScoreError::WrongShape { expected, found } => write!(
+                f,
+                "Wrong shape: expected {}x{} buttons, found {}x{}",
+                expected.0, expected.1, found.0, found.1
+            ),
+            ScoreError::InvalidCommand {
+                button,
+                robot,
+                command,
+            } => write!(
+                f,
+                "Invalid command: {} (button {}, robot {})",
+                command, button, robot
+            ),
+            ScoreError::InvalidAction { turn, button } => {
+                write!(f, "Invalid action at turn {}: {}", turn, button)
+            }
+            ScoreError::TooManyActions { turns, limit } => {
+                write!(f, "Too many actions: {} > {}", turns, limit)
+            }
+        }
     }
 }
 
-pub fn compute_score(input: &Input, out: &Output) -> (i64, String) {
+impl std::error::Error for ScoreError {}
+
+pub fn compute_score(input: &Input, out: &Output) -> (i64, Option<ScoreError>) {
     let (mut score, err, _) = compute_score_details(input, out, out.actions.len());
-    if err.len() > 0 {
+    if err.is_some() {
         score = 0;
     }
     (score, err)
 }
 
+fn check_output(input: &Input, out: &Output) -> Result<(), ScoreError> {
+    let expected = (input.K, input.M);
+    if out.cs.len() != input.K {
+        let cols = out.cs.first().map_or(0, |row| row.len());
+        return Err(ScoreError::WrongShape {
+            expected,
+            found: (out.cs.len(), cols),
+        });
+    }
+    for (button, row) in out.cs.iter().enumerate() {
+        if row.len() != input.M {
+            return Err(ScoreError::WrongShape {
+                expected,
+                found: (out.cs.len(), row.len()),
+            });
+        }
+        for (robot, &command) in row.iter().enumerate() {
+            if command >= CMD.len() {
+                return Err(ScoreError::InvalidCommand {
+                    button,
+                    robot,
+                    command,
+                });
+            }
+        }
+    }
+    if out.actions.len() > 2 * input.N * input.N {
+        return Err(ScoreError::TooManyActions {
+            turns: out.actions.len(),
+            limit: 2 * input.N * input.N,
+        });
+    }
+    Ok(())
+}
+
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct State {
     ps: Vec<(usize, usize)>,
+    #[cfg_attr(feature = "serde", serde(with = "serde_unvisited"))]
     used: Vec<Vec<usize>>,
+    /// Turn at which each cell was first visited (`!0` if never).
+    #[cfg_attr(feature = "serde", serde(with = "serde_unvisited"))]
+    first: Vec<Vec<usize>>,
+}
+
+/// Writes `!0` (unvisited) as `null`.
+#[cfg(feature = "serde")]
+mod serde_unvisited {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(v: &[Vec<usize>], s: S) -> Result<S::Ok, S::Error> {
+        v.iter()
+            .map(|row| {
+                row.iter()
+                    .map(|&x| if x == !0 { None } else { Some(x) })
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>()
+            .serialize(s)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Vec<Vec<usize>>, D::Error> {
+        let v: Vec<Vec<Option<usize>>> = Deserialize::deserialize(d)?;
+        Ok(v.into_iter()
+            .map(|row| row.into_iter().map(|x| x.unwrap_or(!0)).collect())
+            .collect())
+    }
 }
 
 fn can_move(input: &Input, (i, j): (usize, usize), dir: usize) -> bool {
@@ -285,38 +621,315 @@ fn can_move(input: &Input, (i, j): (usize, usize), dir: usize) -> bool {
     }
 }
 
-pub fn compute_score_details(input: &Input, out: &Output, t: usize) -> (i64, String, State) {
-    let mut state = State {
-        ps: input.ps.clone(),
-        used: mat![!0; input.N; input.N],
-    };
-    for i in 0..input.M {
-        state.used[state.ps[i].0][state.ps[i].1] = i;
-    }
-    for &a in &out.actions[..t] {
+impl State {
+    fn new(input: &Input) -> State {
+        let mut state = State {
+            ps: input.ps.clone(),
+            used: mat![!0; input.N; input.N],
+            first: mat![!0; input.N; input.N],
+        };
         for i in 0..input.M {
-            let dir = out.cs[a][i];
-            if can_move(input, state.ps[i], dir) {
-                state.ps[i].0 += DIJ[dir].0;
-                state.ps[i].1 += DIJ[dir].1;
-                state.used[state.ps[i].0][state.ps[i].1] = i;
+            state.used[state.ps[i].0][state.ps[i].1] = i;
+            state.first[state.ps[i].0][state.ps[i].1] = 0;
+        }
+        state
+    }
+
+    /// Robot positions.
+    pub fn positions(&self) -> &[(usize, usize)] {
+        &self.ps
+    }
+
+    /// Robot that last visited each cell (`!0` if never).
+    pub fn visited(&self) -> &[Vec<usize>] {
+        &self.used
+    }
+
+    /// Turn at which each cell was first visited (`!0` if never).
+    pub fn first_visit(&self) -> &[Vec<usize>] {
+        &self.first
+    }
+
+    /// Cells not visited yet, in row-major order.
+    pub fn unvisited_cells(&self) -> Vec<(usize, usize)> {
+        let mut cells = vec![];
+        for (i, row) in self.used.iter().enumerate() {
+            for (j, &u) in row.iter().enumerate() {
+                if u == !0 {
+                    cells.push((i, j));
+                }
             }
         }
+        cells
+    }
+
+    /// Number of visited cells.
+    pub fn coverage(&self) -> usize {
+        self.used.iter().flatten().filter(|&&u| u != !0).count()
     }
-    let mut rem = 0;
-    for i in 0..input.N {
-        for j in 0..input.N {
-            if state.used[i][j] == !0 {
-                rem += 1;
+}
+
+/// What one robot changed in a single step, kept so the step can be undone.
+#[derive(Clone, Debug)]
+struct Change {
+    robot: usize,
+    from: (usize, usize),
+    used: usize,
+    first: usize,
+}
+
+/// Turn-by-turn replay of a button assignment with undo.
+///
+/// `step` and `undo` cost O(M), so analyses that look at the state after
+/// every turn run in time linear in the number of turns.
+#[derive(Clone, Debug)]
+pub struct Simulator<'a> {
+    input: &'a Input,
+    cs: &'a [Vec<usize>],
+    state: State,
+    unvisited: usize,
+    history: Vec<Vec<Change>>,
+}
+
+impl<'a> Simulator<'a> {
+    pub fn new(input: &'a Input, cs: &'a [Vec<usize>]) -> Simulator<'a> {
+        let state = State::new(input);
+        let unvisited = input.N * input.N - state.coverage();
+        Simulator {
+            input,
+            cs,
+            state,
+            unvisited,
+            history: vec![],
+        }
+    }
+
+    /// Presses button `action`. On error, the state is left unchanged.
+    pub fn step(&mut self, action: usize) -> Result<(), ScoreError> {
+        let input = self.input;
+        let turn = self.turn();
+        if action >= input.K || action >= self.cs.len() {
+            return Err(ScoreError::InvalidAction {
+                turn,
+                button: action,
+            });
+        }
+        let row = &self.cs[action];
+        if row.len() != input.M {
+            return Err(ScoreError::WrongShape {
+                expected: (input.K, input.M),
+                found: (self.cs.len(), row.len()),
+            });
+        }
+        if let Some(robot) = row.iter().position(|&c| c >= CMD.len()) {
+            return Err(ScoreError::InvalidCommand {
+                button: action,
+                robot,
+                command: row[robot],
+            });
+        }
+        let state = &mut self.state;
+        let mut changes = vec![];
+        for (i, &dir) in row.iter().enumerate() {
+            let from = state.ps[i];
+            if can_move(input, from, dir) {
+                let (x, y) = (from.0 + DIJ[dir].0, from.1 + DIJ[dir].1);
+                changes.push(Change {
+                    robot: i,
+                    from,
+                    used: state.used[x][y],
+                    first: state.first[x][y],
+                });
+                if state.used[x][y] == !0 {
+                    self.unvisited -= 1;
+                }
+                state.ps[i] = (x, y);
+                state.used[x][y] = i;
+                state.first[x][y].setmin(turn + 1);
+            }
+        }
+        self.history.push(changes);
+        Ok(())
+    }
+
+    /// Reverts the last step. Returns `false` if there is nothing to undo.
+    pub fn undo(&mut self) -> bool {
+        let changes = match self.history.pop() {
+            Some(changes) => changes,
+            None => return false,
+        };
+        for c in changes.into_iter().rev() {
+            let (x, y) = self.state.ps[c.robot];
+            if c.used == !0 {
+                self.unvisited += 1;
+            }
+            self.state.used[x][y] = c.used;
+            self.state.first[x][y] = c.first;
+            self.state.ps[c.robot] = c.from;
+        }
+        true
+    }
+
+    /// Number of steps taken so far.
+    pub fn turn(&self) -> usize {
+        self.history.len()
+    }
+
+    pub fn positions(&self) -> &[(usize, usize)] {
+        self.state.positions()
+    }
+
+    /// Robot that last visited each cell (`!0` if never).
+    pub fn visited(&self) -> &[Vec<usize>] {
+        self.state.visited()
+    }
+
+    /// Turn at which each cell was first visited (`!0` if never).
+    pub fn first_visit(&self) -> &[Vec<usize>] {
+        self.state.first_visit()
+    }
+
+    /// Number of cells not visited yet.
+    pub fn unvisited(&self) -> usize {
+        self.unvisited
+    }
+
+    pub fn score_if_stopped_now(&self) -> i64 {
+        let N = self.input.N;
+        if self.unvisited == 0 {
+            (3 * N * N - self.turn()) as i64
+        } else {
+            (N * N - self.unvisited) as i64
+        }
+    }
+
+    pub fn state(&self) -> &State {
+        &self.state
+    }
+}
+
+/// Simulates the first `t` actions. On error, the state is the one right
+/// before the offending action.
+pub fn compute_score_details(
+    input: &Input,
+    out: &Output,
+    t: usize,
+) -> (i64, Option<ScoreError>, State) {
+    let mut sim = Simulator::new(input, &out.cs);
+    let mut err = check_output(input, out).err();
+    if err.is_none() {
+        for &a in &out.actions[..t] {
+            if let Err(e) = sim.step(a) {
+                err = Some(e);
+                break;
             }
         }
     }
-    let score = if rem == 0 {
+    let score = if sim.unvisited() == 0 {
         (3 * input.N * input.N - t) as i64
     } else {
-        (input.N * input.N - rem) as i64
+        sim.score_if_stopped_now()
+    };
+    (score, err, sim.state)
+}
+
+/// BFS distances from the nearest of `sources` to every cell (`!0` if unreachable).
+pub fn calc_dist(input: &Input, sources: &[(usize, usize)]) -> Vec<Vec<usize>> {
+    let mut dist = mat![!0; input.N; input.N];
+    let mut que = std::collections::VecDeque::new();
+    for &(i, j) in sources {
+        if dist[i][j] != 0 {
+            dist[i][j] = 0;
+            que.push_back((i, j));
+        }
+    }
+    while let Some((i, j)) = que.pop_front() {
+        for (dir, &(di, dj)) in DIJ[..4].iter().enumerate() {
+            if !can_move(input, (i, j), dir) {
+                continue;
+            }
+            let (i2, j2) = (i + di, j + dj);
+            if dist[i2][j2] == !0 {
+                dist[i2][j2] = dist[i][j] + 1;
+                que.push_back((i2, j2));
+            }
+        }
+    }
+    dist
+}
+
+/// Lower bounds on the number of turns needed to visit every reachable cell.
+#[derive(Clone, Debug)]
+pub struct LowerBound {
+    /// Number of cells some robot can reach.
+    pub reachable: usize,
+    /// Each turn, every robot enters at most one new cell.
+    pub coverage: usize,
+    /// Some cell is this far from every start point.
+    pub reach: usize,
+    /// Some robot has to visit two of M + 1 cells that are pairwise this far apart.
+    pub diameter: usize,
+}
+
+impl LowerBound {
+    pub fn turns(&self) -> usize {
+        self.coverage.max(self.reach).max(self.diameter)
+    }
+
+    /// Upper bound of the score achievable for the input. If some cell is
+    /// unreachable, the score is at most the number of reachable cells.
+    pub fn score(&self, input: &Input) -> i64 {
+        if self.reachable < input.N * input.N {
+            self.reachable as i64
+        } else {
+            (3 * input.N * input.N - self.turns()) as i64
+        }
+    }
+}
+
+pub fn compute_lower_bound(input: &Input) -> LowerBound {
+    let mut starts = input.ps.clone();
+    starts.sort();
+    starts.dedup();
+    let dist = calc_dist(input, &input.ps);
+    let reachable = dist.iter().flatten().filter(|&&d| d != !0).count();
+    let coverage = (reachable - starts.len()).div_ceil(input.M);
+    let reach = dist
+        .iter()
+        .flatten()
+        .copied()
+        .filter(|&d| d != !0)
+        .max()
+        .unwrap();
+    // Farthest-point sampling of M + 1 cells, starting from one end of the
+    // (approximate) diameter. Only cells reachable from the first robot are
+    // sampled, as unreachable cells (`!0`) need not be visited.
+    let far = |dist: &Vec<Vec<usize>>| {
+        (0..input.N * input.N)
+            .rev()
+            .map(|c| (c / input.N, c % input.N))
+            .filter(|&(i, j)| dist[i][j] != !0)
+            .max_by_key(|&(i, j)| dist[i][j])
+            .unwrap()
     };
-    (score, String::new(), state)
+    let mut picked = vec![far(&calc_dist(input, &input.ps[..1]))];
+    let mut diameter = !0;
+    while picked.len() <= input.M {
+        let dist = calc_dist(input, &picked);
+        let next = far(&dist);
+        if dist[next.0][next.1] == 0 {
+            diameter = 0;
+            break;
+        }
+        diameter.setmin(dist[next.0][next.1]);
+        picked.push(next);
+    }
+    LowerBound {
+        reachable,
+        coverage,
+        reach,
+        diameter,
+    }
 }
 
 /// 0 <= val <= 1
@@ -346,6 +959,107 @@ pub fn color(mut val: f64) -> String {
     )
 }
 
+/// Optional layers of the visualization.
+#[derive(Clone, Debug, Default)]
+pub struct VisOptions {
+    /// Draw each robot's path (older segments fade out) and mark moves blocked
+    /// by walls.
+    pub trajectory: bool,
+    /// Color cells by the turn they were first visited instead of by robot.
+    pub heatmap: bool,
+}
+
+#[derive(Clone, Debug)]
+pub struct Trace {
+    /// Robot positions after each turn; `ps[0]` is the start.
+    pub ps: Vec<Vec<(usize, usize)>>,
+    /// Moves blocked by walls as (turn, robot, direction).
+    pub blocked: Vec<(usize, usize, usize)>,
+}
+
+/// Replays the first `t` turns, stopping at the first invalid action.
+pub fn trace(input: &Input, out: &Output, t: usize) -> Trace {
+    let mut ps = vec![input.ps.clone()];
+    let mut blocked = vec![];
+    if check_output(input, out).is_err() {
+        return Trace { ps, blocked };
+    }
+    let mut sim = Simulator::new(input, &out.cs);
+    for (turn, &a) in out.actions[..t].iter().enumerate() {
+        if sim.step(a).is_err() {
+            break;
+        }
+        // A robot that did not move although told to was stopped by a wall.
+        for (k, &p) in sim.positions().iter().enumerate() {
+            let dir = out.cs[a][k];
+            if dir != 4 && p == ps[turn][k] {
+                blocked.push((turn, k, dir));
+            }
+        }
+        ps.push(sim.positions().to_vec());
+    }
+    Trace { ps, blocked }
+}
+
+/// Number of most recent turns over which the trajectory fades out.
+pub const TRAIL: usize = 20;
+
+/// Opacity of the trajectory segment ending at turn `s` when turn `t` is
+/// shown. Segments older than [`TRAIL`] turns share the lowest opacity, so
+/// they look the same from one frame to the next.
+pub fn trail_opacity(s: usize, t: usize) -> f64 {
+    0.15 + 0.85 * TRAIL.saturating_sub(t - s) as f64 / TRAIL as f64
+}
+
+fn trajectory_group(input: &Input, out: &Output, t: usize, D: usize) -> Group {
+    let Trace { ps, blocked } = trace(input, out, t);
+    let mut g = Group::new().set("id", "trajectory");
+    for k in 0..input.M {
+        for s in 1..ps.len() {
+            let (a, b) = (ps[s - 1][k], ps[s][k]);
+            if a == b {
+                continue;
+            }
+            g = g.add(
+                Line::new()
+                    .set("x1", a.1 * D + D / 2)
+                    .set("y1", a.0 * D + D / 2)
+                    .set("x2", b.1 * D + D / 2)
+                    .set("y2", b.0 * D + D / 2)
+                    .set("stroke", robot_color(input, k))
+                    .set("stroke-width", 3)
+                    .set("stroke-opacity", trail_opacity(s, ps.len() - 1)),
+            );
+        }
+    }
+    // A short red bar on the side of the cell the robot bumped into.
+    for (turn, k, dir) in blocked {
+        let (i, j) = ps[turn][k];
+        let (x, y) = (j * D, i * D);
+        let (x1, y1, x2, y2) = match dir {
+            0 => (x + D / 4, y + 2, x + D * 3 / 4, y + 2),
+            1 => (x + D / 4, y + D - 2, x + D * 3 / 4, y + D - 2),
+            2 => (x + 2, y + D / 4, x + 2, y + D * 3 / 4),
+            _ => (x + D - 2, y + D / 4, x + D - 2, y + D * 3 / 4),
+        };
+        g = g.add(
+            Line::new()
+                .set("x1", x1)
+                .set("y1", y1)
+                .set("x2", x2)
+                .set("y2", y2)
+                .set("stroke", "red")
+                .set("stroke-width", 3)
+                .set("stroke-opacity", 0.5),
+        );
+    }
+    g
+}
+
+pub fn robot_color(input: &Input, k: usize) -> String {
+    color(k as f64 / (input.M.max(2) - 1) as f64)
+}
+
 pub fn rect(x: usize, y: usize, w: usize, h: usize, fill: &str) -> Rectangle {
     Rectangle::new()
         .set("x", x)
@@ -359,125 +1073,614 @@ pub fn group(title: String) -> Group {
     Group::new().add(Title::new(title))
 }
 
-pub fn vis_default(input: &Input, out: &Output) -> (i64, String, String) {
+/// Robot `k` as a labeled circle in the cell at the origin.
+pub(crate) fn robot_marker(k: usize, D: usize) -> Group {
+    group(format!("robot {}", k))
+        .add(
+            Circle::new()
+                .set("cx", D / 2)
+                .set("cy", D / 2)
+                .set("r", D / 2 - 2)
+                .set("fill", "white")
+                .set("stroke", "black")
+                .set("stroke-width", 1),
+        )
+        .add(
+            Text::new(format!("{}", k))
+                .set("x", D / 2)
+                .set("y", D / 2)
+                .set("font-size", D * 2 / 3)
+                .set("fill", "black"),
+        )
+}
+
+/// Draws the grid with `D` pixels per cell. Cell `(i, j)` is a rectangle with
+/// id `{prefix}c{i * N + j}` whose title and fill are given by `cell`.
+/// `layers` go over the cells, and the grid lines and walls over them.
+pub(crate) fn grid_svg(
+    input: &Input,
+    D: usize,
+    prefix: &str,
+    cell: impl Fn(usize, usize) -> (String, String),
+    layers: Vec<Group>,
+) -> svg::Document {
+    let N = input.N;
+    let mut doc = svg::Document::new()
+        .set("viewBox", (-5, -5, D * N + 10, D * N + 10))
+        .set("width", D * N + 10)
+        .set("height", D * N + 10)
+        .add(Style::new(
+            "text {text-anchor: middle;dominant-baseline: central;}",
+        ));
+    for i in 0..N {
+        for j in 0..N {
+            let (title, fill) = cell(i, j);
+            let id = format!("{}c{}", prefix, i * N + j);
+            doc = doc.add(group(title).add(rect(j * D, i * D, D, D, &fill).set("id", id)));
+        }
+    }
+    for layer in layers {
+        doc = doc.add(layer);
+    }
+    for i in 0..=N {
+        let (stroke, width) = if i == 0 || i == N {
+            ("black", 2)
+        } else {
+            ("lightgray", 1)
+        };
+        for (x1, y1, x2, y2) in [(0, i * D, N * D, i * D), (i * D, 0, i * D, N * D)] {
+            doc = doc.add(
+                Line::new()
+                    .set("x1", x1)
+                    .set("y1", y1)
+                    .set("x2", x2)
+                    .set("y2", y2)
+                    .set("stroke", stroke)
+                    .set("stroke-width", width),
+            );
+        }
+    }
+    for i in 0..N {
+        for j in 0..N {
+            let mut walls = vec![];
+            if j + 1 < N && input.wall_v[i][j] {
+                walls.push((j * D + D, i * D, j * D + D, (i + 1) * D));
+            }
+            if i + 1 < N && input.wall_h[i][j] {
+                walls.push((j * D, i * D + D, (j + 1) * D, i * D + D));
+            }
+            for (x1, y1, x2, y2) in walls {
+                doc = doc.add(
+                    Line::new()
+                        .set("x1", x1)
+                        .set("y1", y1)
+                        .set("x2", x2)
+                        .set("y2", y2)
+                        .set("stroke", "black")
+                        .set("stroke-width", 2),
+                );
+            }
+        }
+    }
+    doc
+}
+
+pub fn vis_default(input: &Input, out: &Output) -> (i64, Option<ScoreError>, String) {
     let (mut score, err, svg) = vis(input, out, out.actions.len());
-    if err.len() > 0 {
+    if err.is_some() {
         score = 0;
     }
     (score, err, svg)
 }
 
-pub fn vis(input: &Input, out: &Output, t: usize) -> (i64, String, String) {
+/// Renders every turn as an HTML fragment with a turn slider and playback
+/// controls. Frames are stored as per-turn changes and drawn by embedded JS.
+pub fn vis_animation(
+    input: &Input,
+    out: &Output,
+    opts: &VisOptions,
+) -> (i64, Option<ScoreError>, String) {
+    let T = out.actions.len();
+    let (mut score, err, svg) = vis_with(input, out, T, opts);
+    if err.is_some() {
+        score = 0;
+    }
+    let mut ps = vec![];
+    let mut diff = vec![];
+    let mut scores = vec![];
+    let mut sim = Simulator::new(input, &out.cs);
+    let mut ok = check_output(input, out).is_ok();
+    for t in 0..=T {
+        let mut d = vec![];
+        if t == 0 {
+            for (c, &now) in sim.visited().iter().flatten().enumerate() {
+                if now != !0 {
+                    d.push(format!("{},{}", c, now));
+                }
+            }
+        } else if ok && sim.step(out.actions[t - 1]).is_ok() {
+            // Only the cells the robots stand on can have changed.
+            for &(i, j) in sim.positions() {
+                d.push(format!("{},{}", i * input.N + j, sim.visited()[i][j]));
+            }
+        } else {
+            ok = false;
+        }
+        diff.push(format!("[{}]", d.join(",")));
+        ps.push(format!(
+            "[{}]",
+            sim.positions()
+                .iter()
+                .map(|&(i, j)| (i * input.N + j).to_string())
+                .collect::<Vec<_>>()
+                .join(",")
+        ));
+        scores.push(sim.score_if_stopped_now().to_string());
+    }
+    let mut heat = vec![];
+    if opts.heatmap {
+        for &first in sim.first_visit().iter().flatten() {
+            heat.push(if first == !0 {
+                "\"white\"".to_owned()
+            } else {
+                format!("[{},\"{}\"]", first, color(first as f64 / T.max(1) as f64))
+            });
+        }
+    }
+    let colors = (0..input.M)
+        .map(|k| format!("\"{}\"", robot_color(input, k)))
+        .collect::<Vec<_>>();
+    let blocked = trace(input, out, T)
+        .blocked
+        .iter()
+        .map(|&(turn, k, dir)| format!("{},{},{}", turn, k, dir))
+        .collect::<Vec<_>>();
+    let html = format!(
+        r#"{controls}<div style="display:flex;gap:16px;align-items:flex-start">
+{svg}
+{table}
+</div>
+<script>
+const data = {{N: {N}, colors: [{colors}], ps: [{ps}], diff: [{diff}], scores: [{scores}],
+  trajectory: {trajectory}, trail: {trail}, blocked: [{blocked}],
+  buttons: {buttons}, actions: [{actions}], heat: [{heat}]}};
+{js}</script>"#,
+        controls = player_controls(T),
+        svg = svg,
+        table = button_table(input, out, T),
+        N = input.N,
+        colors = colors.join(","),
+        ps = ps.join(","),
+        diff = diff.join(","),
+        scores = scores.join(","),
+        trajectory = opts.trajectory,
+        trail = TRAIL,
+        blocked = blocked.join(","),
+        buttons = out.cs.len(),
+        heat = heat.join(","),
+        actions = out
+            .actions
+            .iter()
+            .map(|a| a.to_string())
+            .collect::<Vec<_>>()
+            .join(","),
+        js = concat!(include_str!("player.js"), include_str!("vis.js")),
+    );
+    (score, err, html)
+}
+
+/// Play button, turn slider and speed select for an animation of turns
+/// `0..=T`. The page wires them up with `player` from player.js.
+pub(crate) fn player_controls(T: usize) -> String {
+    format!(
+        r#"<div>
+<button id="play">Play</button>
+<input type="range" id="turn" min="0" max="{T}" value="{T}" style="width:600px">
+<label>speed <select id="speed">
+<option value="5">5</option>
+<option value="10" selected>10</option>
+<option value="30">30</option>
+<option value="60">60</option>
+<option value="120">120</option>
+</select> turns/s</label>
+<span id="turn_label"></span>
+</div>
+"#,
+        T = T
+    )
+}
+
+/// Renders `cs` as an HTML table of buttons x robots. The button pressed at
+/// turn `t` is highlighted and each row shows how many times the button has
+/// been pressed in the first `t` turns.
+pub fn button_table(input: &Input, out: &Output, t: usize) -> String {
+    const ARROWS: [char; 5] = ['↑', '↓', '←', '→', '·'];
+    let t = t.min(out.actions.len());
+    let mut counts = vec![0; out.cs.len()];
+    for &a in &out.actions[..t] {
+        if a < counts.len() {
+            counts[a] += 1;
+        }
+    }
+    let pressed = t.checked_sub(1).map(|t| out.actions[t]);
+    let mut html = String::from(
+        "<table id=\"buttons\" style=\"border-collapse:collapse;text-align:center\">\n<tr><th></th>",
+    );
+    for k in 0..input.M {
+        html += &format!(
+            "<th style=\"background-color:{};padding:2px 6px\">{}</th>",
+            robot_color(input, k),
+            k
+        );
+    }
+    html += "<th>presses</th></tr>\n";
+    for (i, row) in out.cs.iter().enumerate() {
+        html += &format!(
+            "<tr id=\"b{}\"{}><th>{}</th>",
+            i,
+            if pressed == Some(i) {
+                " style=\"background-color:yellow\""
+            } else {
+                ""
+            },
+            i
+        );
+        for &c in row {
+            html += &format!("<td>{}</td>", ARROWS.get(c).unwrap_or(&'?'));
+        }
+        html += &format!("<td id=\"n{}\">{}</td></tr>\n", i, counts[i]);
+    }
+    html += "</table>";
+    html
+}
+
+pub fn vis(input: &Input, out: &Output, t: usize) -> (i64, Option<ScoreError>, String) {
+    vis_with(input, out, t, &VisOptions::default())
+}
+
+pub fn vis_with(
+    input: &Input,
+    out: &Output,
+    t: usize,
+    opts: &VisOptions,
+) -> (i64, Option<ScoreError>, String) {
     let D = 600 / input.N;
-    let W = D * input.N;
-    let H = D * input.N;
     let (score, err, state) = compute_score_details(input, out, t);
-    let mut doc = svg::Document::new()
+    let mut layers = vec![];
+    if opts.trajectory {
+        layers.push(trajectory_group(input, out, t, D));
+    }
+    // Later robots are drawn on top.
+    for k in 0..input.M {
+        let (i, j) = state.ps[k];
+        layers.push(
+            robot_marker(k, D)
+                .set("id", format!("r{}", k))
+                .set("transform", format!("translate({},{})", j * D, i * D)),
+        );
+    }
+    let cell = |i: usize, j: usize| {
+        let title = if opts.heatmap && state.first[i][j] != !0 {
+            format!("({}, {})\nfirst visit: {}", i, j, state.first[i][j])
+        } else {
+            format!("({}, {})", i, j)
+        };
+        let fill = if state.used[i][j] == !0 {
+            "white".to_owned()
+        } else if opts.heatmap {
+            color(state.first[i][j] as f64 / t.max(1) as f64)
+        } else {
+            robot_color(input, state.used[i][j])
+        };
+        (title, fill)
+    };
+    let doc = grid_svg(input, D, "", cell, layers)
         .set("id", "vis")
-        .set("viewBox", (-5, -5, W + 10, H + 10))
-        .set("width", W + 10)
-        .set("height", H + 10)
         .set("style", "background-color:white");
-    doc = doc.add(Style::new(format!(
-        "text {{text-anchor: middle;dominant-baseline: central;}}"
-    )));
-    for i in 0..input.N {
-        for j in 0..input.N {
-            let mut group = group(format!("({}, {})", i, j)).add(rect(
-                j * D,
-                i * D,
-                D,
-                D,
-                &if state.used[i][j] == !0 {
-                    "white".to_owned()
-                } else {
-                    color(state.used[i][j] as f64 / (input.M - 1) as f64)
-                },
-            ));
-            if let Some(k) = (0..input.M).rfind(|&k| state.ps[k] == (i, j)) {
-                group = group.add(
-                    Circle::new()
-                        .set("cx", j * D + D / 2)
-                        .set("cy", i * D + D / 2)
-                        .set("r", D / 2 - 2)
-                        .set("fill", "white")
-                        .set("stroke", "black")
-                        .set("stroke-width", 1),
-                );
-                group = group.add(
-                    Text::new(format!("{}", k))
-                        .set("x", j * D + D / 2)
-                        .set("y", i * D + D / 2)
-                        .set("font-size", D * 2 / 3)
-                        .set("fill", "black"),
-                );
+    (score, err, doc.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn score_errors() {
+        let input = gen(0);
+        let out = Output {
+            cs: mat![0; input.K; input.M],
+            actions: vec![0, 1, 2],
+        };
+        assert_eq!(compute_score(&input, &out).1, None);
+
+        let mut bad = Output {
+            cs: mat![0; input.K - 1; input.M],
+            actions: vec![],
+        };
+        assert_eq!(
+            compute_score(&input, &bad).1,
+            Some(ScoreError::WrongShape {
+                expected: (input.K, input.M),
+                found: (input.K - 1, input.M),
+            })
+        );
+        bad.cs = mat![0; input.K; input.M];
+        bad.cs[3].pop();
+        assert!(matches!(
+            compute_score(&input, &bad).1,
+            Some(ScoreError::WrongShape { found: (_, 9), .. })
+        ));
+        bad.cs = mat![0; input.K; input.M];
+        bad.cs[3][4] = 5;
+        assert_eq!(
+            compute_score(&input, &bad).1,
+            Some(ScoreError::InvalidCommand {
+                button: 3,
+                robot: 4,
+                command: 5
+            })
+        );
+        bad.cs[3][4] = 0;
+        bad.actions = vec![0, 1, input.K, 0];
+        let (score, err, _) = compute_score_details(&input, &bad, 4);
+        assert_eq!(
+            err,
+            Some(ScoreError::InvalidAction {
+                turn: 2,
+                button: input.K
+            })
+        );
+        assert!(score > 0);
+        assert_eq!(compute_score(&input, &bad).0, 0);
+        bad.actions = vec![0; 2 * input.N * input.N + 1];
+        assert!(matches!(
+            compute_score(&input, &bad).1,
+            Some(ScoreError::TooManyActions { .. })
+        ));
+    }
+
+    #[test]
+    fn first_visit() {
+        let input = gen(0);
+        let out = Output {
+            cs: mat![1; input.K; input.M],
+            actions: vec![0; 5],
+        };
+        let (_, _, state) = compute_score_details(&input, &out, 5);
+        for &(i, j) in &input.ps {
+            assert_eq!(state.first[i][j], 0);
+        }
+        for i in 0..input.N {
+            for j in 0..input.N {
+                assert_eq!(state.first[i][j] == !0, state.used[i][j] == !0);
+                if state.first[i][j] != !0 {
+                    assert!(state.first[i][j] <= 5);
+                }
             }
-            doc = doc.add(group);
+        }
+        // Every button moves every robot down.
+        let (i, j) = input.ps[0];
+        if i + 1 < input.N && !input.wall_h[i][j] {
+            assert!(state.first[i + 1][j] <= 1);
         }
     }
-    for i in 0..=input.N {
-        doc = doc.add(
-            Line::new()
-                .set("x1", 0)
-                .set("y1", i * D)
-                .set("x2", input.N * D)
-                .set("y2", i * D)
-                .set(
-                    "stroke",
-                    if i == 0 || i == input.N {
-                        "black"
-                    } else {
-                        "lightgray"
-                    },
-                )
-                .set("stroke-width", if i == 0 || i == input.N { 2 } else { 1 }),
+
+    #[test]
+    fn state_accessors() {
+        let input = gen(0);
+        let out = Output {
+            cs: mat![3; input.K; input.M],
+            actions: vec![0; 4],
+        };
+        let (score, _, state) = compute_score_details(&input, &out, 4);
+        assert_eq!(state.positions().len(), input.M);
+        let unvisited = state.unvisited_cells();
+        assert_eq!(unvisited.len() + state.coverage(), input.N * input.N);
+        assert_eq!(state.coverage() as i64, score);
+        for &(i, j) in &unvisited {
+            assert_eq!(state.visited()[i][j], !0);
+            assert_eq!(state.first_visit()[i][j], !0);
+        }
+        for &(i, j) in state.positions() {
+            assert_ne!(state.visited()[i][j], !0);
+        }
+    }
+
+    #[test]
+    fn simulator_step_and_undo() {
+        let mut rng = rand_chacha::ChaCha20Rng::seed_from_u64(0);
+        let input = gen(1);
+        let out = Output {
+            cs: (0..input.K)
+                .map(|_| (0..input.M).map(|_| rng.gen_range(0..5)).collect())
+                .collect(),
+            actions: (0..200).map(|_| rng.gen_range(0..input.K)).collect(),
+        };
+        let mut sim = Simulator::new(&input, &out.cs);
+        let mut states = vec![sim.state().clone()];
+        for (t, &a) in out.actions.iter().enumerate() {
+            sim.step(a).unwrap();
+            let (score, _, state) = compute_score_details(&input, &out, t + 1);
+            assert_eq!(sim.turn(), t + 1);
+            assert_eq!(sim.positions(), &state.ps[..]);
+            assert_eq!(sim.visited(), &state.used[..]);
+            assert_eq!(sim.first_visit(), &state.first[..]);
+            assert_eq!(sim.score_if_stopped_now(), score);
+            states.push(state);
+        }
+        while sim.turn() > 0 {
+            assert!(sim.undo());
+            let state = &states[sim.turn()];
+            assert_eq!(sim.positions(), &state.ps[..]);
+            assert_eq!(sim.visited(), &state.used[..]);
+            assert_eq!(sim.first_visit(), &state.first[..]);
+        }
+        assert!(!sim.undo());
+        assert_eq!(
+            sim.score_if_stopped_now(),
+            compute_score_details(&input, &out, 0).0
         );
-        doc = doc.add(
-            Line::new()
-                .set("x1", i * D)
-                .set("y1", 0)
-                .set("x2", i * D)
-                .set("y2", input.N * D)
-                .set(
-                    "stroke",
-                    if i == 0 || i == input.N {
-                        "black"
-                    } else {
-                        "lightgray"
-                    },
-                )
-                .set("stroke-width", if i == 0 || i == input.N { 2 } else { 1 }),
+        assert_eq!(
+            sim.step(input.K),
+            Err(ScoreError::InvalidAction {
+                turn: 0,
+                button: input.K
+            })
         );
     }
-    for i in 0..input.N {
-        for j in 0..input.N {
-            if j + 1 < input.N {
-                if input.wall_v[i][j] {
-                    doc = doc.add(
-                        Line::new()
-                            .set("x1", j * D + D)
-                            .set("y1", i * D)
-                            .set("x2", j * D + D)
-                            .set("y2", (i + 1) * D)
-                            .set("stroke", "black")
-                            .set("stroke-width", 2),
-                    );
-                }
-            }
-            if i + 1 < input.N {
-                if input.wall_h[i][j] {
-                    doc = doc.add(
-                        Line::new()
-                            .set("x1", j * D)
-                            .set("y1", i * D + D)
-                            .set("x2", (j + 1) * D)
-                            .set("y2", i * D + D)
-                            .set("stroke", "black")
-                            .set("stroke-width", 2),
-                    );
-                }
-            }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn state_json_round_trip() {
+        let input = gen(0);
+        let out = Output {
+            cs: mat![1; input.K; input.M],
+            actions: vec![0; 3],
+        };
+        let (_, _, state) = compute_score_details(&input, &out, 3);
+        let json = serde_json::to_string(&state).unwrap();
+        assert!(json.contains("null"));
+        let back: State = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.ps, state.ps);
+        assert_eq!(back.used, state.used);
+        assert_eq!(back.first, state.first);
+    }
+
+    #[test]
+    fn output_round_trip() {
+        let mut rng = rand_chacha::ChaCha20Rng::seed_from_u64(0);
+        for _ in 0..100 {
+            let N = rng.gen_range(2..=30);
+            let M = rng.gen_range(1..=10);
+            let K = rng.gen_range(1..=10);
+            let input = Input {
+                N,
+                M,
+                K,
+                ps: vec![(0, 0); M],
+                wall_v: mat![false; N; N - 1],
+                wall_h: mat![false; N - 1; N],
+            };
+            let T = rng.gen_range(0..=2 * N * N);
+            let out = Output {
+                cs: (0..K)
+                    .map(|_| (0..M).map(|_| rng.gen_range(0..5)).collect())
+                    .collect(),
+                actions: (0..T).map(|_| rng.gen_range(0..K)).collect(),
+            };
+            assert_eq!(parse_output(&input, &out.to_string()), Ok(out));
         }
     }
-    (score, err, doc.to_string())
+
+    #[test]
+    fn parse_output_rejects() {
+        let mut input = gen(0);
+        input.M = 2;
+        input.K = 2;
+        let cmd = "(U, D, L, R or S)";
+        for (f, err) in [
+            (
+                "U D\nL\n",
+                format!("line 2, column 2: expected command of button 1 for robot 1 {}, found end of file", cmd),
+            ),
+            (
+                "U D\nL X\n",
+                format!("line 2, column 3: expected command of button 1 for robot 1 {}, found \"X\"", cmd),
+            ),
+            (
+                "U D\nLR S\n",
+                format!("line 2, column 1: expected command of button 1 for robot 0 {}, found \"LR\"", cmd),
+            ),
+            (
+                "U D\nL R\n0\n  2\n",
+                "line 4, column 3: expected button (integer in 0..2), found \"2\"".to_owned(),
+            ),
+            (
+                "U D\r\nL R\r\n0 -1\r\n",
+                "line 3, column 3: expected button (integer in 0..2), found \"-1\"".to_owned(),
+            ),
+        ] {
+            assert_eq!(parse_output(&input, f).unwrap_err().to_string(), err, "{:?}", f);
+        }
+        let f = format!("U D\nL R\n{}", "0\n".repeat(2 * input.N * input.N + 1));
+        let err = parse_output(&input, &f).unwrap_err();
+        assert_eq!((err.line, err.column), (2 * input.N * input.N + 3, 1));
+        assert_eq!(err.expected, "end of file (at most 1800 actions)");
+        let f = format!("U D\nL R\n{}", "0\n".repeat(2 * input.N * input.N));
+        assert!(parse_output(&input, &f).is_ok());
+        // An invalid command is written as a placeholder instead of panicking.
+        let out = Output {
+            cs: vec![vec![0, 7], vec![2, 3]],
+            actions: vec![0],
+        };
+        assert_eq!(out.to_string(), "U ?\nL R\n0\n");
+        let err = parse_output(&input, &out.to_string()).unwrap_err();
+        assert_eq!((err.line, err.column, err.found.as_str()), (1, 3, "\"?\""));
+    }
+
+    #[test]
+    fn parse_input_round_trip_and_rejects() {
+        let input = gen(0);
+        let f = input.to_string();
+        assert_eq!(parse_input(&f).unwrap().to_string(), f);
+        let lines = f.lines().collect::<Vec<_>>();
+        let with_line = |i: usize, line: &str| {
+            let mut lines = lines.clone();
+            lines[i] = line;
+            lines.join("\n")
+        };
+        let err = parse_input(&with_line(0, "30 0 10")).unwrap_err();
+        assert_eq!((err.line, err.column), (1, 4));
+        assert_eq!(err.expected, "M (integer in 1..=900)");
+        let err = parse_input(&with_line(3, "5 30")).unwrap_err();
+        assert_eq!((err.line, err.column), (4, 3));
+        assert_eq!(err.expected, "column of robot 2 (integer in 0..30)");
+        let err = parse_input(&with_line(12, "0001")).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "line 13, column 1: expected row 1 of vertical walls (29 characters of 0/1), found \"0001\""
+        );
+        let err = parse_input(&lines[..lines.len() - 1].join("\n")).unwrap_err();
+        assert_eq!(err.found, "end of file");
+        assert_eq!(err.line, lines.len() - 1);
+    }
+
+    #[test]
+    fn lower_bound_on_open_grid() {
+        let open = |ps: Vec<(usize, usize)>| Input {
+            N: 4,
+            M: ps.len(),
+            K: 1,
+            ps,
+            wall_v: mat![false; 4; 3],
+            wall_h: mat![false; 3; 4],
+        };
+        // One robot in a corner: 15 cells to enter, the far corner is 6 away.
+        let lb = compute_lower_bound(&open(vec![(0, 0)]));
+        assert_eq!((lb.coverage, lb.reach, lb.diameter), (15, 6, 6));
+        assert_eq!(lb.turns(), 15);
+        assert_eq!(lb.score(&open(vec![(0, 0)])), 33);
+        // Opposite corners: the other two corners are 3 away from both.
+        let lb = compute_lower_bound(&open(vec![(0, 0), (3, 3)]));
+        assert_eq!((lb.coverage, lb.reach, lb.diameter), (7, 3, 3));
+        // A wall between (0, 0) and (0, 1) forces a detour through row 1.
+        let mut input = open(vec![(0, 0)]);
+        input.wall_v[0][0] = true;
+        assert_eq!(calc_dist(&input, &[(0, 0)])[0][1], 3);
+        // Walled into (0, 0): only that cell counts and no turn is needed.
+        let mut input = open(vec![(0, 0)]);
+        input.wall_v[0][0] = true;
+        input.wall_h[0][0] = true;
+        let lb = compute_lower_bound(&input);
+        assert_eq!(lb.reachable, 1);
+        assert_eq!((lb.coverage, lb.reach, lb.diameter), (0, 0, 0));
+        assert_eq!(lb.score(&input), 1);
+        // A second robot reaches the rest; (0, 1) is 5 away from (3, 3).
+        input.ps.push((3, 3));
+        input.M = 2;
+        let lb = compute_lower_bound(&input);
+        assert_eq!(lb.reachable, 16);
+        assert_eq!((lb.coverage, lb.reach, lb.diameter), (7, 5, 0));
+        assert_eq!(lb.score(&input), 48 - 7);
+    }
 }
@@ -0,0 +1,352 @@
+//! Hand-written inputs: a builder and an ASCII-art maze format.
+//!
+//! In the maze format every cell is three characters followed by the wall on
+//! its right (`|` or a space). The three characters are `___` if there is a
+//! wall below the cell and spaces otherwise; the middle one is replaced by the
+//! label of the robot starting there. Robots are labeled `0`-`9`, then `a`-`z`
+//! and `A`-`Z`. The first line is the top border. For example
+//!
+//! ```text
+//!  ___ ___ ___
+//! | 0     |   |
+//! |___ _1_|   |
+//! |___ ___ ___|
+//! ```
+//!
+//! is a 3x3 grid with robot 0 at (0, 0) and robot 1 at (1, 1), walls below
+//! (1, 0) and (1, 1), and walls right of (0, 1) and (1, 1). Borders are not
+//! checked when parsing, and trailing spaces may be omitted.
+
+use crate::*;
+
+const LABELS: &str = "0123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+/// Builds an [`Input`] cell by cell. Starts with no walls and no robots.
+/// Mistakes are reported by [`InputBuilder::build`].
+#[derive(Clone, Debug)]
+pub struct InputBuilder {
+    N: usize,
+    K: usize,
+    ps: Vec<(usize, usize)>,
+    wall_v: Vec<Vec<bool>>,
+    wall_h: Vec<Vec<bool>>,
+    /// The first wall added outside the grid.
+    bad_wall: Option<String>,
+}
+
+impl InputBuilder {
+    /// An `N` x `N` grid with the official number of buttons.
+    pub fn new(N: usize) -> Self {
+        InputBuilder {
+            N,
+            K: GenConfig::default().K,
+            ps: vec![],
+            wall_v: mat![false; N; N.saturating_sub(1)],
+            wall_h: mat![false; N.saturating_sub(1); N],
+            bad_wall: None,
+        }
+    }
+
+    pub fn buttons(mut self, K: usize) -> Self {
+        self.K = K;
+        self
+    }
+
+    /// Adds the next robot at `(i, j)`.
+    pub fn robot(mut self, i: usize, j: usize) -> Self {
+        self.ps.push((i, j));
+        self
+    }
+
+    /// Adds a wall between `(i, j)` and `(i, j + 1)`.
+    pub fn wall_right(mut self, i: usize, j: usize) -> Self {
+        match self.wall_v.get_mut(i).and_then(|row| row.get_mut(j)) {
+            Some(w) => *w = true,
+            None => self.outside(format!("Wall right of {:?}", (i, j))),
+        }
+        self
+    }
+
+    /// Adds a wall between `(i, j)` and `(i + 1, j)`.
+    pub fn wall_below(mut self, i: usize, j: usize) -> Self {
+        match self.wall_h.get_mut(i).and_then(|row| row.get_mut(j)) {
+            Some(w) => *w = true,
+            None => self.outside(format!("Wall below {:?}", (i, j))),
+        }
+        self
+    }
+
+    fn outside(&mut self, wall: String) {
+        self.bad_wall
+            .get_or_insert_with(|| format!("{} is outside the grid", wall));
+    }
+
+    /// Parses the ASCII maze format described in the module documentation.
+    pub fn from_maze(f: &str) -> Result<Self, String> {
+        let mut lines = f.lines().collect::<Vec<_>>();
+        while lines.last().is_some_and(|l| l.trim().is_empty()) {
+            lines.pop();
+        }
+        let N = lines.len().saturating_sub(1);
+        if N < 2 {
+            return Err(format!("Expected at least 2 rows, found {}", N));
+        }
+        let mut b = InputBuilder::new(N);
+        let mut robots = vec![None; LABELS.len()];
+        for (i, line) in lines[1..].iter().enumerate() {
+            let line = line.chars().collect::<Vec<_>>();
+            if line.len() > 4 * N + 1 {
+                return Err(format!(
+                    "line {}: expected at most {} characters, found {}",
+                    i + 2,
+                    4 * N + 1,
+                    line.len()
+                ));
+            }
+            let at = |k: usize| line.get(k).copied().unwrap_or(' ');
+            for j in 0..N {
+                let col = 4 * j + 1;
+                let (left, mid, right) = (at(col), at(col + 1), at(col + 2));
+                let bad = |k: usize| {
+                    format!(
+                        "line {}, column {}: unexpected {:?} in cell ({}, {})",
+                        i + 2,
+                        col + k + 1,
+                        at(col + k),
+                        i,
+                        j
+                    )
+                };
+                if left != ' ' && left != '_' {
+                    return Err(bad(0));
+                }
+                if right != left {
+                    return Err(bad(2));
+                }
+                if let Some(k) = LABELS.find(mid) {
+                    if let Some(prev) = robots[k] {
+                        return Err(format!(
+                            "line {}: robot {} is at both {:?} and {:?}",
+                            i + 2,
+                            mid,
+                            prev,
+                            (i, j)
+                        ));
+                    }
+                    robots[k] = Some((i, j));
+                } else if mid != left {
+                    return Err(bad(1));
+                }
+                if i + 1 < N && left == '_' {
+                    b = b.wall_below(i, j);
+                }
+                let sep = at(col + 3);
+                if j + 1 < N {
+                    match sep {
+                        '|' => b = b.wall_right(i, j),
+                        ' ' => {}
+                        _ => return Err(bad(3)),
+                    }
+                }
+            }
+        }
+        let M = robots.iter().take_while(|p| p.is_some()).count();
+        if let Some(k) = robots[M..].iter().position(|p| p.is_some()) {
+            return Err(format!(
+                "Robot {} is missing but robot {} exists",
+                &LABELS[M..M + 1],
+                &LABELS[M + k..M + k + 1]
+            ));
+        }
+        for &(i, j) in robots[..M].iter().flatten() {
+            b = b.robot(i, j);
+        }
+        Ok(b)
+    }
+
+    /// Checks the constraints of the official input format.
+    pub fn build(self) -> Result<Input, String> {
+        let N = self.N;
+        if N < 2 {
+            return Err(format!("N must be at least 2: {}", N));
+        }
+        if self.ps.is_empty() {
+            return Err("No robots".to_owned());
+        }
+        if self.K == 0 {
+            return Err("K must be at least 1".to_owned());
+        }
+        if let Some(err) = self.bad_wall {
+            return Err(err);
+        }
+        for (k, &(i, j)) in self.ps.iter().enumerate() {
+            if i >= N || j >= N {
+                return Err(format!("Robot {} is outside the grid: {:?}", k, (i, j)));
+            }
+            if let Some(l) = self.ps[..k].iter().position(|&p| p == (i, j)) {
+                return Err(format!("Robots {} and {} are both at {:?}", l, k, (i, j)));
+            }
+        }
+        Ok(Input {
+            N,
+            M: self.ps.len(),
+            K: self.K,
+            ps: self.ps,
+            wall_v: self.wall_v,
+            wall_h: self.wall_h,
+        })
+    }
+}
+
+/// Parses the ASCII maze format into an input with `K` buttons.
+pub fn parse_maze(f: &str, K: usize) -> Result<Input, String> {
+    InputBuilder::from_maze(f)?.buttons(K).build()
+}
+
+/// Writes `input` in the ASCII maze format. Fails if there are more robots
+/// than labels.
+pub fn to_maze(input: &Input) -> Result<String, String> {
+    let N = input.N;
+    if input.M > LABELS.len() {
+        return Err(format!(
+            "At most {} robots can be labeled: {}",
+            LABELS.len(),
+            input.M
+        ));
+    }
+    let mut f = " ___".repeat(N);
+    f.push('\n');
+    for i in 0..N {
+        f.push('|');
+        for j in 0..N {
+            let floor = if i + 1 == N || input.wall_h[i][j] {
+                '_'
+            } else {
+                ' '
+            };
+            f.push(floor);
+            let robot = input.ps.iter().position(|&p| p == (i, j));
+            f.push(robot.map_or(floor, |k| LABELS.as_bytes()[k] as char));
+            f.push(floor);
+            f.push(if j + 1 == N || input.wall_v[i][j] {
+                '|'
+            } else {
+                ' '
+            });
+        }
+        f.push('\n');
+    }
+    Ok(f)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "
+ ___ ___ ___
+| 0     |   |
+|___ _1_|   |
+|___ ___ ___|
+";
+
+    #[test]
+    fn builder_matches_maze() {
+        let built = InputBuilder::new(3)
+            .buttons(2)
+            .robot(0, 0)
+            .robot(1, 1)
+            .wall_below(1, 0)
+            .wall_below(1, 1)
+            .wall_right(0, 1)
+            .wall_right(1, 1)
+            .build()
+            .unwrap();
+        let parsed = parse_maze(EXAMPLE.trim_start_matches('\n'), 2).unwrap();
+        assert_eq!(parsed.to_string(), built.to_string());
+        assert_eq!(to_maze(&built).unwrap(), EXAMPLE.trim_start_matches('\n'));
+    }
+
+    #[test]
+    fn round_trip() {
+        for seed in 0..10 {
+            let input = gen(seed);
+            let maze = to_maze(&input).unwrap();
+            let back = parse_maze(&maze, input.K).unwrap();
+            assert_eq!(back.to_string(), input.to_string());
+            // Trailing spaces may be stripped.
+            let trimmed = maze
+                .lines()
+                .map(str::trim_end)
+                .collect::<Vec<_>>()
+                .join("\n");
+            assert_eq!(
+                parse_maze(&trimmed, input.K).unwrap().to_string(),
+                input.to_string()
+            );
+        }
+    }
+
+    #[test]
+    fn walls_block_robots() {
+        let input = parse_maze(
+            " ___ ___ ___
+| 0 |       |
+|   |       |
+|___|_1_ ___|",
+            1,
+        )
+        .unwrap();
+        // Button 0 moves robot 0 right (blocked) and robot 1 up.
+        let out = Output {
+            cs: vec![vec![3, 0]],
+            actions: vec![0, 0],
+        };
+        let (score, err, state) = compute_score_details(&input, &out, 2);
+        assert!(err.is_none());
+        assert_eq!(state.positions(), &[(0, 0), (0, 1)]);
+        assert_eq!(score, 4);
+    }
+
+    #[test]
+    fn rejects() {
+        let cases = [
+            (" ___ ___\n| 0 |   |\n", "Expected at least 2 rows, found 1"),
+            (
+                " ___ ___\n| 0 |   |\n|_*_ ___|\n",
+                "line 3, column 3: unexpected '*' in cell (1, 0)",
+            ),
+            (
+                " ___ ___\n| 0 |   |\n|__  ___|\n",
+                "line 3, column 4: unexpected ' ' in cell (1, 0)",
+            ),
+            (
+                " ___ ___\n| 0 #   |\n|___ ___|\n",
+                "line 2, column 5: unexpected '#' in cell (0, 0)",
+            ),
+            (
+                " ___ ___\n| 0 | 0 |\n|___ ___|\n",
+                "line 2: robot 0 is at both (0, 0) and (0, 1)",
+            ),
+            (
+                " ___ ___\n| 0 | 2 |\n|___ ___|\n",
+                "Robot 1 is missing but robot 2 exists",
+            ),
+            (" ___ ___\n|   |   |\n|___ ___|\n", "No robots"),
+        ];
+        for (f, err) in cases {
+            assert_eq!(parse_maze(f, 1).unwrap_err(), err, "{:?}", f);
+        }
+        let err = InputBuilder::new(2).robot(0, 0).robot(0, 0).build();
+        assert_eq!(err.unwrap_err(), "Robots 0 and 1 are both at (0, 0)");
+        // The last column has no wall on its right, the last row none below.
+        let err = InputBuilder::new(2)
+            .robot(0, 0)
+            .wall_right(0, 1)
+            .wall_below(5, 0)
+            .build();
+        assert_eq!(err.unwrap_err(), "Wall right of (0, 1) is outside the grid");
+        let err = InputBuilder::new(2).robot(0, 0).wall_below(1, 0).build();
+        assert_eq!(err.unwrap_err(), "Wall below (1, 0) is outside the grid");
+    }
+}
@@ -0,0 +1,38 @@
+// Play button, turn slider and speed select from player_controls in lib.rs.
+// `show(t)` draws turn `t` and moves the slider to it.
+function player(last, show) {
+  const slider = document.getElementById("turn");
+  const play = document.getElementById("play");
+  const speed = document.getElementById("speed");
+  let timer = null;
+
+  function stop() {
+    clearInterval(timer);
+    timer = null;
+    play.textContent = "Play";
+  }
+
+  function start() {
+    if (+slider.value >= last) {
+      show(0);
+    }
+    timer = setInterval(() => {
+      const t = +slider.value + 1;
+      show(t);
+      if (t >= last) {
+        stop();
+      }
+    }, 1000 / +speed.value);
+    play.textContent = "Pause";
+  }
+
+  slider.addEventListener("input", () => show(+slider.value));
+  play.addEventListener("click", () => (timer === null ? start() : stop()));
+  speed.addEventListener("change", () => {
+    if (timer !== null) {
+      stop();
+      start();
+    }
+  });
+  show(last);
+}
@@ -0,0 +1,416 @@
+//! Raster rendering of the visualization: PNG for a single turn and animated
+//! GIF for a whole run. Draws the same layers as the SVG renderer.
+
+use crate::*;
+
+/// Margin around the grid, as in the SVG viewBox.
+const MARGIN: usize = 5;
+
+/// 3x5 bitmaps of the digits, one row per 3 bits from the top.
+const DIGITS: [[u8; 5]; 10] = [
+    [7, 5, 5, 5, 7],
+    [2, 6, 2, 2, 7],
+    [7, 1, 7, 4, 7],
+    [7, 1, 7, 1, 7],
+    [5, 5, 7, 1, 1],
+    [7, 4, 7, 1, 7],
+    [7, 4, 7, 5, 7],
+    [7, 1, 1, 1, 1],
+    [7, 5, 7, 5, 7],
+    [7, 5, 7, 1, 7],
+];
+
+/// Parses the colors used by the SVG renderer: a few names and `#rrggbb[aa]`.
+fn parse_color(c: &str) -> [u8; 4] {
+    match c {
+        "white" => [255, 255, 255, 255],
+        "black" => [0, 0, 0, 255],
+        "lightgray" => [211, 211, 211, 255],
+        "red" => [255, 0, 0, 255],
+        _ => {
+            let hex = |k: usize| u8::from_str_radix(&c[1 + 2 * k..3 + 2 * k], 16).unwrap();
+            [
+                hex(0),
+                hex(1),
+                hex(2),
+                if c.len() == 9 { hex(3) } else { 255 },
+            ]
+        }
+    }
+}
+
+struct Canvas {
+    W: usize,
+    H: usize,
+    /// RGB, row-major.
+    pixels: Vec<u8>,
+}
+
+impl Canvas {
+    fn new(W: usize, H: usize) -> Self {
+        Canvas {
+            W,
+            H,
+            pixels: vec![255; W * H * 3],
+        }
+    }
+
+    fn blend(&mut self, x: usize, y: usize, color: [u8; 4], opacity: f64) {
+        if x >= self.W || y >= self.H {
+            return;
+        }
+        let a = color[3] as f64 / 255.0 * opacity;
+        let p = &mut self.pixels[(y * self.W + x) * 3..][..3];
+        for (p, &c) in p.iter_mut().zip(&color[..3]) {
+            *p = (*p as f64 * (1.0 - a) + c as f64 * a).round() as u8;
+        }
+    }
+
+    /// Fills `w` x `h` pixels from grid coordinates `(x, y)`.
+    fn fill_rect(&mut self, x: usize, y: usize, w: usize, h: usize, color: &str, opacity: f64) {
+        let color = parse_color(color);
+        for py in y..y + h {
+            for px in x..x + w {
+                self.blend(px + MARGIN, py + MARGIN, color, opacity);
+            }
+        }
+    }
+
+    /// Fills the pixels whose centers are within `r` of `(cx, cy)`.
+    fn fill_circle(&mut self, cx: f64, cy: f64, r: f64, color: &str) {
+        let color = parse_color(color);
+        let y0 = (cy - r).floor().max(0.0) as usize;
+        let x0 = (cx - r).floor().max(0.0) as usize;
+        for py in y0..(cy + r).ceil() as usize {
+            for px in x0..(cx + r).ceil() as usize {
+                let (dx, dy) = (px as f64 + 0.5 - cx, py as f64 + 0.5 - cy);
+                if dx * dx + dy * dy <= r * r {
+                    self.blend(px + MARGIN, py + MARGIN, color, 1.0);
+                }
+            }
+        }
+    }
+
+    /// Draws `text` (digits only) centered at `(cx, cy)`, `5 * scale` pixels tall.
+    fn digits(&mut self, cx: usize, cy: usize, text: &str, scale: usize) {
+        let w = text.len() * 4 * scale - scale;
+        let x0 = cx.saturating_sub(w / 2);
+        let y0 = cy.saturating_sub(5 * scale / 2);
+        for (k, c) in text.bytes().enumerate() {
+            let glyph = DIGITS[(c - b'0') as usize];
+            for (r, bits) in glyph.iter().enumerate() {
+                for b in 0..3 {
+                    if bits >> (2 - b) & 1 != 0 {
+                        let x = x0 + (k * 4 + b) * scale;
+                        self.fill_rect(x, y0 + r * scale, scale, scale, "black", 1.0);
+                    }
+                }
+            }
+        }
+    }
+
+    fn to_png(&self) -> Vec<u8> {
+        let mut buf = vec![];
+        let mut encoder = png::Encoder::new(&mut buf, self.W as u32, self.H as u32);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header().unwrap();
+        writer.write_image_data(&self.pixels).unwrap();
+        writer.finish().unwrap();
+        buf
+    }
+
+    /// Smallest rectangle `(x, y, w, h)` containing every pixel that differs
+    /// from `prev`, or `None` if the canvases are equal.
+    fn changed(&self, prev: &Canvas) -> Option<(usize, usize, usize, usize)> {
+        let (mut x0, mut y0, mut x1, mut y1) = (self.W, self.H, 0, 0);
+        for y in 0..self.H {
+            for x in 0..self.W {
+                let k = (y * self.W + x) * 3;
+                if self.pixels[k..k + 3] != prev.pixels[k..k + 3] {
+                    x0.setmin(x);
+                    y0.setmin(y);
+                    x1.setmax(x + 1);
+                    y1.setmax(y + 1);
+                }
+            }
+        }
+        (x0 < x1).then(|| (x0, y0, x1 - x0, y1 - y0))
+    }
+
+    /// Palette and indices of a rectangle for a GIF frame. Beyond 256 distinct
+    /// colors, pixels get the nearest color already in the palette. Pixels
+    /// equal to `prev` get index 0, which is reserved as transparent so the
+    /// previous frame shows through.
+    fn to_indexed(
+        &self,
+        (x, y, w, h): (usize, usize, usize, usize),
+        prev: Option<&Canvas>,
+    ) -> (Vec<u8>, Vec<u8>) {
+        let mut palette: Vec<[u8; 3]> = vec![];
+        if prev.is_some() {
+            palette.push([0; 3]);
+        }
+        let mut index = std::collections::HashMap::new();
+        let mut indices = Vec::with_capacity(w * h);
+        let mut last = None;
+        for r in y..y + h {
+            let range = (r * self.W + x) * 3..(r * self.W + x + w) * 3;
+            let old = prev.map(|prev| &prev.pixels[range.clone()]);
+            for (k, p) in self.pixels[range].chunks(3).enumerate() {
+                if old.is_some_and(|old| old[k * 3..k * 3 + 3] == *p) {
+                    indices.push(0);
+                    continue;
+                }
+                let c = [p[0], p[1], p[2]];
+                // Runs of one color are common; skip the lookup for them.
+                if let Some((prev, i)) = last {
+                    if prev == c {
+                        indices.push(i);
+                        continue;
+                    }
+                }
+                let i = *index.entry(c).or_insert_with(|| {
+                    if palette.len() < 256 {
+                        palette.push(c);
+                        palette.len() - 1
+                    } else {
+                        let dist = |q: &[u8; 3]| {
+                            (0..3)
+                                .map(|k| (q[k] as i32 - c[k] as i32).pow(2))
+                                .sum::<i32>()
+                        };
+                        (prev.is_some() as usize..palette.len())
+                            .min_by_key(|&i| dist(&palette[i]))
+                            .unwrap()
+                    }
+                }) as u8;
+                last = Some((c, i));
+                indices.push(i);
+            }
+        }
+        (palette.concat(), indices)
+    }
+}
+
+/// Draws `state` after `t` turns. `trace` may extend beyond `t`; heatmap
+/// colors are scaled by `heat_scale` turns.
+fn draw(
+    input: &Input,
+    state: &State,
+    t: usize,
+    trace: &Trace,
+    heat_scale: usize,
+    opts: &VisOptions,
+    size: usize,
+) -> Canvas {
+    let N = input.N;
+    let D = (size / N).max(1);
+    let mut canvas = Canvas::new(D * N + 2 * MARGIN, D * N + 2 * MARGIN);
+    let used = state.visited();
+    let first = state.first_visit();
+    for i in 0..N {
+        for j in 0..N {
+            let fill = if used[i][j] == !0 {
+                "white".to_owned()
+            } else if opts.heatmap {
+                color(first[i][j] as f64 / heat_scale.max(1) as f64)
+            } else {
+                robot_color(input, used[i][j])
+            };
+            canvas.fill_rect(j * D, i * D, D, D, &fill, 1.0);
+        }
+    }
+    if opts.trajectory {
+        let ps = &trace.ps[..trace.ps.len().min(t + 1)];
+        for k in 0..input.M {
+            for s in 1..ps.len() {
+                let (a, b) = (ps[s - 1][k], ps[s][k]);
+                if a == b {
+                    continue;
+                }
+                let (i, j) = (a.0.min(b.0), a.1.min(b.1));
+                let (h, w) = (a.0.abs_diff(b.0) * D + 3, a.1.abs_diff(b.1) * D + 3);
+                let opacity = trail_opacity(s, ps.len() - 1);
+                let (x, y) = (
+                    (j * D + D / 2).saturating_sub(1),
+                    (i * D + D / 2).saturating_sub(1),
+                );
+                canvas.fill_rect(x, y, w, h, &robot_color(input, k), opacity);
+            }
+        }
+        for &(turn, k, dir) in trace.blocked.iter().filter(|b| b.0 < t) {
+            let (i, j) = ps[turn][k];
+            let (x, y) = (j * D, i * D);
+            let (x, y, w, h) = match dir {
+                0 => (x + D / 4, y + 1, D / 2, 3),
+                1 => (x + D / 4, y + D - 3, D / 2, 3),
+                2 => (x + 1, y + D / 4, 3, D / 2),
+                _ => (x + D - 3, y + D / 4, 3, D / 2),
+            };
+            canvas.fill_rect(x, y, w, h, "red", 0.5);
+        }
+    }
+    for (k, &(i, j)) in state.positions().iter().enumerate() {
+        let (cx, cy) = ((j * D + D / 2) as f64, (i * D + D / 2) as f64);
+        let r = (D / 2).saturating_sub(2) as f64;
+        canvas.fill_circle(cx, cy, r + 0.5, "black");
+        canvas.fill_circle(cx, cy, r - 0.5, "white");
+        canvas.digits(
+            j * D + D / 2,
+            i * D + D / 2,
+            &k.to_string(),
+            (D / 10).max(1),
+        );
+    }
+    for i in 0..=N {
+        if i == 0 || i == N {
+            canvas.fill_rect((i * D).saturating_sub(1), 0, 2, N * D, "black", 1.0);
+            canvas.fill_rect(0, (i * D).saturating_sub(1), N * D, 2, "black", 1.0);
+        } else {
+            canvas.fill_rect(i * D, 0, 1, N * D, "lightgray", 1.0);
+            canvas.fill_rect(0, i * D, N * D, 1, "lightgray", 1.0);
+        }
+    }
+    for i in 0..N {
+        for j in 0..N {
+            if j + 1 < N && input.wall_v[i][j] {
+                canvas.fill_rect(j * D + D - 1, i * D, 2, D, "black", 1.0);
+            }
+            if i + 1 < N && input.wall_h[i][j] {
+                canvas.fill_rect(j * D, i * D + D - 1, D, 2, "black", 1.0);
+            }
+        }
+    }
+    canvas
+}
+
+/// Renders the state after `t` turns as a PNG image about `size` pixels wide.
+pub fn vis_png(
+    input: &Input,
+    out: &Output,
+    t: usize,
+    opts: &VisOptions,
+    size: usize,
+) -> (i64, Option<ScoreError>, Vec<u8>) {
+    let (score, err, state) = compute_score_details(input, out, t);
+    let trace = if opts.trajectory {
+        trace(input, out, t)
+    } else {
+        Trace {
+            ps: vec![],
+            blocked: vec![],
+        }
+    };
+    let canvas = draw(input, &state, t, &trace, t, opts, size);
+    (score, err, canvas.to_png())
+}
+
+/// Renders every turn as an animated GIF about `size` pixels wide. Turns are
+/// skipped evenly so that there are at most `max_frames` frames; the last
+/// turn is always shown.
+pub fn vis_gif(
+    input: &Input,
+    out: &Output,
+    opts: &VisOptions,
+    size: usize,
+    max_frames: usize,
+) -> (i64, Option<ScoreError>, Vec<u8>) {
+    let T = out.actions.len();
+    let (mut score, err) = compute_score(input, out);
+    if err.is_some() {
+        score = 0;
+    }
+    let trace = trace(input, out, T);
+    let step = T.div_ceil(max_frames.max(2) - 1).max(1);
+    let mut sim = Simulator::new(input, &out.cs);
+    let mut ok = check_output(input, out).is_ok();
+    let mut buf = vec![];
+    {
+        let side = ((size / input.N).max(1) * input.N + 2 * MARGIN) as u16;
+        let mut encoder = gif::Encoder::new(&mut buf, side, side, &[]).unwrap();
+        encoder.set_repeat(gif::Repeat::Infinite).unwrap();
+        let mut prev: Option<Canvas> = None;
+        for t in 0..=T {
+            if t > 0 {
+                ok = ok && sim.step(out.actions[t - 1]).is_ok();
+            }
+            if t % step != 0 && t != T {
+                continue;
+            }
+            let canvas = draw(input, sim.state(), t, &trace, T, opts, size);
+            // Each frame is drawn over the previous one, so only the changed
+            // rectangle is stored and unchanged pixels in it are transparent.
+            let rect = match &prev {
+                Some(prev) => canvas.changed(prev).unwrap_or((0, 0, 1, 1)),
+                None => (0, 0, canvas.W, canvas.H),
+            };
+            let (palette, indices) = canvas.to_indexed(rect, prev.as_ref());
+            let (w, h) = (rect.2 as u16, rect.3 as u16);
+            let transparent = prev.is_some().then_some(0);
+            let mut frame = gif::Frame::from_palette_pixels(w, h, indices, palette, transparent);
+            frame.left = rect.0 as u16;
+            frame.top = rect.1 as u16;
+            // Hundredths of a second; hold the final state.
+            frame.delay = if t == T { 300 } else { 5 };
+            encoder.write_frame(&frame).unwrap();
+            prev = Some(canvas);
+        }
+    }
+    (score, err, buf)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn png_and_gif() {
+        let input = gen(0);
+        let out = Output {
+            cs: (0..input.K)
+                .map(|b| (0..input.M).map(|k| (b + k) % 5).collect())
+                .collect(),
+            actions: (0..20).map(|t| t * 7 % input.K).collect(),
+        };
+        let opts = VisOptions {
+            trajectory: true,
+            heatmap: true,
+        };
+        let (score, err, png) = vis_png(&input, &out, 20, &opts, 300);
+        assert_eq!((score, err), compute_score(&input, &out));
+        let mut reader = png::Decoder::new(&png[..]).read_info().unwrap();
+        // 10px cells plus margins.
+        assert_eq!((reader.info().width, reader.info().height), (310, 310));
+        let mut expected = vec![0; reader.output_buffer_size()];
+        reader.next_frame(&mut expected).unwrap();
+
+        let (_, err, gif) = vis_gif(&input, &out, &opts, 300, 5);
+        assert!(err.is_none());
+        let mut options = gif::DecodeOptions::new();
+        options.set_color_output(gif::ColorOutput::RGBA);
+        let mut decoder = options.read_info(&gif[..]).unwrap();
+        assert_eq!((decoder.width(), decoder.height()), (310, 310));
+        // Compose the frames, keeping the pixels under transparent ones; the
+        // result must be the final state.
+        let mut image = vec![0; 310 * 310 * 3];
+        let mut frames = 0;
+        while let Some(frame) = decoder.read_next_frame().unwrap() {
+            let (left, top) = (frame.left as usize, frame.top as usize);
+            for (k, p) in frame.buffer.chunks(4).enumerate() {
+                if p[3] == 0 {
+                    continue;
+                }
+                let (x, y) = (
+                    left + k % frame.width as usize,
+                    top + k / frame.width as usize,
+                );
+                image[(y * 310 + x) * 3..][..3].copy_from_slice(&p[..3]);
+            }
+            frames += 1;
+        }
+        // Turns 0, 5, 10, 15 and 20.
+        assert_eq!(frames, 5);
+        assert!(image == expected);
+    }
+}
@@ -0,0 +1,92 @@
+(() => {
+  const owner = new Array(data.N * data.N);
+  const slider = document.getElementById("turn");
+  const label = document.getElementById("turn_label");
+  const D = 600 / data.N | 0;
+
+  function show(t) {
+    owner.fill(-1);
+    for (let s = 0; s <= t; s++) {
+      const d = data.diff[s];
+      for (let i = 0; i < d.length; i += 2) {
+        owner[d[i]] = d[i + 1];
+      }
+    }
+    for (let c = 0; c < owner.length; c++) {
+      let fill = owner[c] < 0 ? "white" : data.colors[owner[c]];
+      if (data.heat.length > 0) {
+        // [first visit turn, color], colored on the scale of the last turn.
+        fill = data.heat[c] !== "white" && data.heat[c][0] <= t ? data.heat[c][1] : "white";
+      }
+      document.getElementById("c" + c).setAttribute("fill", fill);
+    }
+    data.ps[t].forEach((c, k) => {
+      const x = c % data.N * D, y = (c / data.N | 0) * D;
+      document.getElementById("r" + k).setAttribute("transform", `translate(${x},${y})`);
+    });
+    if (data.trajectory) {
+      drawTrajectory(t);
+    }
+    drawButtons(t);
+    slider.value = t;
+    label.textContent = `turn ${t} / ${data.ps.length - 1}, score = ${data.scores[t]}`;
+  }
+
+  function line(x1, y1, x2, y2, stroke, opacity) {
+    const l = document.createElementNS("http://www.w3.org/2000/svg", "line");
+    for (const [k, v] of Object.entries({x1, y1, x2, y2, stroke})) {
+      l.setAttribute(k, v);
+    }
+    l.setAttribute("stroke-width", 3);
+    l.setAttribute("stroke-opacity", opacity);
+    return l;
+  }
+
+  // Same drawing as trajectory_group in lib.rs.
+  function drawTrajectory(t) {
+    const g = document.getElementById("trajectory");
+    const lines = [];
+    const center = (c) => [c % data.N * D + (D / 2 | 0), (c / data.N | 0) * D + (D / 2 | 0)];
+    for (let k = 0; k < data.colors.length; k++) {
+      for (let s = 1; s <= t; s++) {
+        const a = data.ps[s - 1][k], b = data.ps[s][k];
+        if (a !== b) {
+          lines.push(line(...center(a), ...center(b), data.colors[k],
+            0.15 + 0.85 * Math.max(0, data.trail - (t - s)) / data.trail));
+        }
+      }
+    }
+    const q = D / 4 | 0, q3 = D * 3 / 4 | 0;
+    for (let i = 0; i < data.blocked.length; i += 3) {
+      const [turn, k, dir] = data.blocked.slice(i, i + 3);
+      if (turn >= t) {
+        break;
+      }
+      const c = data.ps[turn][k];
+      const x = c % data.N * D, y = (c / data.N | 0) * D;
+      const ends = [
+        [x + q, y + 2, x + q3, y + 2],
+        [x + q, y + D - 2, x + q3, y + D - 2],
+        [x + 2, y + q, x + 2, y + q3],
+        [x + D - 2, y + q, x + D - 2, y + q3],
+      ][dir];
+      lines.push(line(...ends, "red", 0.5));
+    }
+    g.replaceChildren(...lines);
+  }
+
+  // Same highlighting and counts as button_table in lib.rs.
+  function drawButtons(t) {
+    const counts = [];
+    for (let s = 0; s < t; s++) {
+      counts[data.actions[s]] = (counts[data.actions[s]] || 0) + 1;
+    }
+    for (let i = 0; i < data.buttons; i++) {
+      const pressed = t > 0 && data.actions[t - 1] === i;
+      document.getElementById("b" + i).style.backgroundColor = pressed ? "yellow" : "";
+      document.getElementById("n" + i).textContent = counts[i] || 0;
+    }
+  }
+
+  player(data.ps.length - 1, show);
+})();
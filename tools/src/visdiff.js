@@ -0,0 +1,39 @@
+(() => {
+  const slider = document.getElementById("turn");
+  const label = document.getElementById("turn_label");
+  const cursor = document.getElementById("cursor");
+  const D = data.D;
+
+  // A cell is highlighted in an output's color from the turn that output
+  // covers it, if the other covers it later or never.
+  function showOne(p, o, other, color, t) {
+    const s = Math.min(t, o.ps.length - 1);
+    for (let c = 0; c < data.N * data.N; c++) {
+      const f = o.first[c], g = other.first[c];
+      let fill = "white";
+      if (f >= 0 && f <= t) {
+        fill = g < 0 || f < g ? color : "#dddddd";
+      }
+      document.getElementById(p + "c" + c).setAttribute("fill", fill);
+    }
+    o.ps[s].forEach((c, k) => {
+      const x = c % data.N * D, y = (c / data.N | 0) * D;
+      document.getElementById(p + "r" + k).setAttribute("transform", `translate(${x},${y})`);
+    });
+    document.getElementById("label_" + p).textContent =
+      `coverage = ${o.coverage[s]}, score at turn = ${o.scores[s]}`;
+  }
+
+  function show(t) {
+    showOne("a", data.a, data.b, data.colors[0] + "a0", t);
+    showOne("b", data.b, data.a, data.colors[1] + "a0", t);
+    // Same scale as chart in visdiff.rs.
+    const x = t * data.W / Math.max(data.T, 1);
+    cursor.setAttribute("x1", x);
+    cursor.setAttribute("x2", x);
+    slider.value = t;
+    label.textContent = `turn ${t} / ${data.T}`;
+  }
+
+  player(data.T, show);
+})();
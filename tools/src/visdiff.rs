@@ -0,0 +1,204 @@
+//! Side-by-side comparison of two outputs for the same input.
+
+use crate::*;
+
+/// Width of each grid in pixels.
+const SIZE: usize = 400;
+/// Colors of A and B, in the chart and for cells one covers earlier.
+const COLORS: [&str; 2] = ["#1e90ff", "#ff8c00"];
+
+/// Everything the page needs to show one output at any turn.
+struct Replay {
+    score: i64,
+    err: Option<ScoreError>,
+    /// Robot positions (as cell indices) after each valid turn.
+    ps: Vec<Vec<usize>>,
+    scores: Vec<i64>,
+    coverage: Vec<usize>,
+    /// Turn at which each cell was first visited (`!0` if never).
+    first: Vec<usize>,
+}
+
+fn replay(input: &Input, out: &Output) -> Replay {
+    let (mut score, err) = compute_score(input, out);
+    if err.is_some() {
+        score = 0;
+    }
+    let mut sim = Simulator::new(input, &out.cs);
+    let mut ps = vec![];
+    let mut scores = vec![];
+    let mut coverage = vec![];
+    for t in 0..=out.actions.len() {
+        if t > 0 && sim.step(out.actions[t - 1]).is_err() {
+            break;
+        }
+        ps.push(
+            sim.positions()
+                .iter()
+                .map(|&(i, j)| i * input.N + j)
+                .collect(),
+        );
+        scores.push(sim.score_if_stopped_now());
+        coverage.push(input.N * input.N - sim.unvisited());
+    }
+    Replay {
+        score,
+        err,
+        ps,
+        scores,
+        coverage,
+        first: sim.first_visit().concat(),
+    }
+}
+
+/// Cells, robots and walls of one grid. Element ids start with `p`.
+fn grid(input: &Input, p: &str) -> String {
+    let robots = (0..input.M)
+        .map(|k| robot_marker(k, SIZE / input.N).set("id", format!("{}r{}", p, k)))
+        .collect();
+    let cell = |i, j| (format!("({}, {})", i, j), "white".to_owned());
+    grid_svg(input, SIZE / input.N, p, cell, robots).to_string()
+}
+
+/// Coverage of both outputs over time, with a cursor moved by the page.
+fn chart(input: &Input, replays: &[Replay; 2], T: usize) -> String {
+    let (W, H) = (2 * SIZE, 150);
+    let x = |t: usize| t as f64 * W as f64 / T.max(1) as f64;
+    let y = |c: usize| H as f64 * (1.0 - c as f64 / (input.N * input.N) as f64);
+    let mut doc = svg::Document::new()
+        .set("viewBox", (-5, -5, W + 10, H + 10))
+        .set("width", W + 10)
+        .set("height", H + 10)
+        .add(rect(0, 0, W, H, "white").set("stroke", "lightgray"));
+    for (r, color) in replays.iter().zip(COLORS) {
+        let points = r
+            .coverage
+            .iter()
+            .enumerate()
+            .map(|(t, &c)| format!("{:.1},{:.1}", x(t), y(c)))
+            .collect::<Vec<_>>();
+        doc = doc.add(
+            svg::node::element::Polyline::new()
+                .set("points", points.join(" "))
+                .set("fill", "none")
+                .set("stroke", color)
+                .set("stroke-width", 2),
+        );
+    }
+    doc.add(
+        Line::new()
+            .set("id", "cursor")
+            .set("y1", 0)
+            .set("y2", H)
+            .set("stroke", "black"),
+    )
+    .to_string()
+}
+
+fn join<T: ToString>(v: impl IntoIterator<Item = T>) -> String {
+    v.into_iter()
+        .map(|x| x.to_string())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Renders both outputs side by side with a shared turn slider and a coverage
+/// chart. Cells an output covers earlier than the other are highlighted.
+/// Returns the score and error of each output, as `vis_default` does.
+pub fn vis_diff(input: &Input, a: &Output, b: &Output) -> ([(i64, Option<ScoreError>); 2], String) {
+    let replays = [replay(input, a), replay(input, b)];
+    let T = a.actions.len().max(b.actions.len());
+    let json = |r: &Replay| {
+        format!(
+            "{{ps: [{}], scores: [{}], coverage: [{}], first: [{}]}}",
+            join(r.ps.iter().map(|p| format!("[{}]", join(p)))),
+            join(&r.scores),
+            join(&r.coverage),
+            // Never visited (`!0`) becomes -1.
+            join(r.first.iter().map(|&f| f as i64)),
+        )
+    };
+    let header = replays
+        .iter()
+        .zip(["A", "B"])
+        .zip(COLORS)
+        .map(|((r, name), color)| {
+            let err = r
+                .err
+                .as_ref()
+                .map(|e| format!(" <span style=\"color:red\">{}</span>", e))
+                .unwrap_or_default();
+            format!(
+                "<div><b style=\"color:{}\">{}</b>: Score = {}{} <span id=\"label_{}\"></span></div>",
+                color,
+                name,
+                r.score,
+                err,
+                name.to_lowercase()
+            )
+        })
+        .collect::<String>();
+    let html = format!(
+        r#"{header}
+{controls}<div style="display:flex;gap:16px">
+<div id="a">{grid_a}</div>
+<div id="b">{grid_b}</div>
+</div>
+{chart}
+<script>
+const data = {{N: {N}, D: {D}, T: {T}, W: {W}, colors: ["{color_a}", "{color_b}"],
+  a: {a}, b: {b}}};
+{js}</script>"#,
+        controls = player_controls(T),
+        grid_a = grid(input, "a"),
+        grid_b = grid(input, "b"),
+        chart = chart(input, &replays, T),
+        N = input.N,
+        D = SIZE / input.N,
+        W = 2 * SIZE,
+        color_a = COLORS[0],
+        color_b = COLORS[1],
+        a = json(&replays[0]),
+        b = json(&replays[1]),
+        js = concat!(include_str!("player.js"), include_str!("visdiff.js")),
+    );
+    let [a, b] = replays;
+    ([(a.score, a.err), (b.score, b.err)], html)
+}
+
+/// Number of cells A covers strictly earlier than B, and vice versa. A cell
+/// one output never covers counts for the other.
+pub fn earlier_counts(input: &Input, a: &Output, b: &Output) -> (usize, usize) {
+    let (a, b) = (replay(input, a).first, replay(input, b).first);
+    let a_first = a.iter().zip(&b).filter(|(x, y)| x < y).count();
+    let b_first = a.iter().zip(&b).filter(|(x, y)| y < x).count();
+    (a_first, b_first)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn earlier_counts_are_antisymmetric() {
+        let input = gen(0);
+        let a = Output {
+            cs: mat![3; input.K; input.M],
+            actions: vec![0; 10],
+        };
+        let b = Output {
+            cs: mat![1; input.K; input.M],
+            actions: vec![0; 10],
+        };
+        assert_eq!(earlier_counts(&input, &a, &a), (0, 0));
+        let (x, y) = earlier_counts(&input, &a, &b);
+        assert!(x > 0 && y > 0);
+        assert_eq!(earlier_counts(&input, &b, &a), (y, x));
+
+        let ([(score_a, _), (score_b, _)], html) = vis_diff(&input, &a, &b);
+        assert_eq!(score_a, compute_score(&input, &a).0);
+        assert_eq!(score_b, compute_score(&input, &b).0);
+        assert!(html.contains("id=\"ac0\"") && html.contains("id=\"bc0\""));
+        assert!(html.contains("id=\"play\"") && html.contains("function player("));
+    }
+}
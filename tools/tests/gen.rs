@@ -0,0 +1,208 @@
+#![allow(non_snake_case)]
+
+use tools::*;
+
+const SEEDS: u64 = 300;
+
+/// (column or row, length) of each line that contains a wall.
+type Lines = Vec<(usize, usize)>;
+
+/// Vertical and horizontal wall lines.
+fn wall_lines(input: &Input) -> (Lines, Lines) {
+    let N = input.N;
+    let v = (0..N - 1)
+        .map(|j| (j, (0..N).filter(|&i| input.wall_v[i][j]).count()))
+        .filter(|&(_, l)| l > 0)
+        .collect();
+    let h = (0..N - 1)
+        .map(|i| (i, (0..N).filter(|&j| input.wall_h[i][j]).count()))
+        .filter(|&(_, l)| l > 0)
+        .collect();
+    (v, h)
+}
+
+#[test]
+fn official_shape() {
+    for seed in 0..SEEDS {
+        let input = gen(seed);
+        assert_eq!((input.N, input.M, input.K), (30, 10, 10));
+        assert_eq!(input.ps.len(), input.M);
+        assert_eq!(input.wall_v.len(), input.N);
+        assert!(input.wall_v.iter().all(|row| row.len() == input.N - 1));
+        assert_eq!(input.wall_h.len(), input.N - 1);
+        assert!(input.wall_h.iter().all(|row| row.len() == input.N));
+    }
+}
+
+#[test]
+fn connected() {
+    for seed in 0..SEEDS {
+        let input = gen(seed);
+        let dist = calc_dist(&input, &[(0, 0)]);
+        assert!(dist.iter().flatten().all(|&d| d != !0), "seed = {}", seed);
+    }
+}
+
+#[test]
+fn distinct_starts() {
+    for seed in 0..SEEDS {
+        let input = gen(seed);
+        let mut ps = input.ps.clone();
+        ps.sort();
+        ps.dedup();
+        assert_eq!(ps.len(), input.M, "seed = {}", seed);
+        assert!(ps.iter().all(|&(i, j)| i < input.N && j < input.N));
+    }
+}
+
+#[test]
+fn wall_spacing() {
+    for seed in 0..SEEDS {
+        let input = gen(seed);
+        let (v, h) = wall_lines(&input);
+        assert_eq!(v.len() + h.len(), 5, "seed = {}", seed);
+        for lines in [&v, &h] {
+            for &(x, l) in lines.iter() {
+                assert!((4..=input.N - 6).contains(&x), "seed = {}", seed);
+                assert!((1..=20).contains(&l), "seed = {}", seed);
+            }
+            // Each wall excludes a band 9 lines wide centered on it.
+            for w in lines.windows(2) {
+                assert!(w[1].0 - w[0].0 >= 5, "seed = {}", seed);
+            }
+        }
+    }
+}
+
+#[test]
+fn deterministic() {
+    for seed in 0..20 {
+        assert_eq!(gen(seed).to_string(), gen(seed).to_string());
+    }
+    assert_ne!(gen(0).to_string(), gen(1).to_string());
+}
+
+#[test]
+fn distribution_fingerprint() {
+    // FNV-1a over the first inputs. Update only when the distribution is
+    // changed on purpose.
+    let mut hash = 0xcbf29ce484222325u64;
+    for seed in 0..10 {
+        for b in gen(seed).to_string().bytes() {
+            hash ^= b as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+    }
+    assert_eq!(hash, 10818093031699265614);
+}
+
+#[test]
+fn custom_shape() {
+    let configs = [
+        GenConfig {
+            N: 2,
+            M: 1,
+            K: 1,
+            walls: 0,
+            ..GenConfig::default()
+        },
+        GenConfig {
+            N: 7,
+            M: 5,
+            K: 3,
+            walls: 2,
+            min_len: 1,
+            max_len: 3,
+            spacing: 2,
+        },
+        GenConfig {
+            N: 12,
+            M: 20,
+            K: 4,
+            walls: 3,
+            min_len: 2,
+            max_len: 5,
+            spacing: 3,
+        },
+    ];
+    for config in &configs {
+        for seed in 0..50 {
+            let input = gen_with(seed, config).unwrap();
+            assert_eq!((input.N, input.M, input.K), (config.N, config.M, config.K));
+            let mut ps = input.ps.clone();
+            ps.sort();
+            ps.dedup();
+            assert_eq!(ps.len(), config.M);
+            let dist = calc_dist(&input, &[(0, 0)]);
+            assert!(dist.iter().flatten().all(|&d| d != !0), "{:?}", config);
+        }
+    }
+}
+
+#[test]
+fn custom_walls() {
+    let config = GenConfig {
+        N: 20,
+        walls: 4,
+        min_len: 3,
+        max_len: 6,
+        spacing: 3,
+        ..GenConfig::default()
+    };
+    for seed in 0..SEEDS {
+        let input = gen_with(seed, &config).unwrap();
+        let (v, h) = wall_lines(&input);
+        // Each direction has room for at least 3 walls.
+        assert_eq!(v.len() + h.len(), 4, "seed = {}", seed);
+        for lines in [&v, &h] {
+            for &(x, l) in lines.iter() {
+                assert!((2..=16).contains(&x), "seed = {}", seed);
+                // Walls are cut at the border.
+                assert!((1..=6).contains(&l), "seed = {}", seed);
+            }
+            for w in lines.windows(2) {
+                assert!(w[1].0 - w[0].0 >= 3, "seed = {}", seed);
+            }
+        }
+    }
+}
+
+#[test]
+fn stops_when_no_line_is_free() {
+    // With spacing 3 on a 6x6 grid, one wall in each direction uses up every
+    // line, so the other three walls are dropped.
+    let config = GenConfig {
+        N: 6,
+        walls: 5,
+        min_len: 1,
+        max_len: 1,
+        spacing: 3,
+        ..GenConfig::default()
+    };
+    for seed in 0..SEEDS {
+        let input = gen_with(seed, &config).unwrap();
+        assert_eq!(wall_lines(&input), (vec![(2, 1)], vec![(2, 1)]));
+    }
+}
+
+#[test]
+fn rejects_impossible_configs() {
+    let config = GenConfig {
+        N: 4,
+        spacing: 3,
+        ..GenConfig::default()
+    };
+    assert_eq!(
+        gen_with(0, &config).unwrap_err(),
+        "spacing must be in 1..=2: 3"
+    );
+    // Both walls reach the border and always cut off a corner.
+    let config = GenConfig {
+        N: 8,
+        walls: 2,
+        spacing: 4,
+        ..GenConfig::default()
+    };
+    let err = gen_with(0, &config).unwrap_err();
+    assert!(err.starts_with("No connected maze"), "{}", err);
+}
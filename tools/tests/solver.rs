@@ -0,0 +1,68 @@
+//! Compares the submitted solver with the exact solver on tiny instances.
+#![allow(non_snake_case, dead_code, clippy::all)]
+
+// The solver is a single file for submission, so it is included as is.
+include!("../../a.rs");
+
+use tools::*;
+
+fn run_solver(input: &Input, planner: Planner) -> Output {
+    let f = input.to_string();
+    input! {
+        f,
+        n: usize, m: usize, k: usize,
+        ij: [(usize, usize); m],
+        v: [chars; n],
+        h: [chars; n - 1],
+    }
+    let (alloc, ops) = solve(n, m, k, &ij, &v, &h, planner);
+    let mut out = String::new();
+    for b in 0..k {
+        let row = alloc.iter().map(|a| a[b].to_string()).collect::<Vec<_>>();
+        out += &format!("{}\n", row.join(" "));
+    }
+    for o in ops {
+        out += &format!("{}\n", o);
+    }
+    parse_output(input, &out).unwrap()
+}
+
+#[test]
+fn never_beats_exact() {
+    for (N, M, K) in [
+        (4, 1, 1),
+        (4, 1, 3),
+        (4, 3, 1),
+        (4, 2, 2),
+        (4, 2, 3),
+        (5, 2, 2),
+    ] {
+        let config = GenConfig {
+            N,
+            M,
+            K,
+            walls: 2,
+            min_len: 2,
+            max_len: 3,
+            spacing: 2,
+        };
+        for seed in 0..3 {
+            let input = gen_with(seed, &config).unwrap();
+            let exact = exact::solve_exact(&input, 10_000_000).unwrap();
+            let (best, err) = compute_score(&input, &exact);
+            assert!(err.is_none());
+            for planner in [Planner::Nearest, Planner::Matching] {
+                let (score, err) = compute_score(&input, &run_solver(&input, planner));
+                assert!(err.is_none(), "{:?}\n{}", planner, input);
+                assert!(
+                    score <= best,
+                    "{:?}: {} > {}\n{}",
+                    planner,
+                    score,
+                    best,
+                    input
+                );
+            }
+        }
+    }
+}